@@ -1,67 +1,97 @@
-use image::{GenericImageView, GrayImage, Rgb, RgbImage};
+use std::{cmp::Ordering, fmt::Display};
+
+use image::{GenericImageView, GrayImage, ImageBuffer, Luma, Pixel, Rgb, Rgb32FImage, RgbImage};
 use rayon::iter::ParallelIterator;
 
+use crate::math::{Matrix, Vector};
+
+const RED: usize = 0;
+const GREEN: usize = 1;
+const BLUE: usize = 2;
+
+/// The layout of the 2x2 tile of a colour filter array, read left to right
+/// then top to bottom from the top-left pixel of the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CfaPattern {
+    Rggb,
+    Bggr,
+    Grbg,
+    /// Green at (0, 0) and (1, 1), blue at (1, 0) and red at (0, 1).
+    #[default]
+    Gbrg,
+}
+
+impl CfaPattern {
+    pub const ALL: [CfaPattern; 4] = [
+        CfaPattern::Rggb,
+        CfaPattern::Bggr,
+        CfaPattern::Grbg,
+        CfaPattern::Gbrg,
+    ];
+
+    /// The RGB channel sampled at (x, y): 0 for red, 1 for green and 2 for
+    /// blue. Out-of-bounds coordinates continue the pattern.
+    pub fn channel(&self, x: i32, y: i32) -> usize {
+        let tile = match self {
+            CfaPattern::Rggb => [[RED, GREEN], [GREEN, BLUE]],
+            CfaPattern::Bggr => [[BLUE, GREEN], [GREEN, RED]],
+            CfaPattern::Grbg => [[GREEN, RED], [BLUE, GREEN]],
+            CfaPattern::Gbrg => [[GREEN, BLUE], [RED, GREEN]],
+        };
+        tile[y.rem_euclid(2) as usize][x.rem_euclid(2) as usize]
+    }
+}
+
 /// Convert a normal RGB image to a Bayer color filter array.
-pub fn cast_rgb_to_bayer_mosaic(img: &RgbImage) -> GrayImage {
+pub fn cast_rgb_to_bayer_mosaic(img: &RgbImage, pattern: CfaPattern) -> GrayImage {
     let bayer_gray_raw = img
         .enumerate_pixels()
-        .map(|(x, y, &Rgb([red, green, blue]))| match (x % 2, y % 2) {
-            (0, 0) | (1, 1) => green,
-            (1, 0) => blue,
-            (0, 1) => red,
-            _ => unreachable!(),
-        });
-
-    let gray = GrayImage::from_vec(img.width(), img.height(), bayer_gray_raw.collect())
-        .expect("Fail to convert RGB image to Gray.");
+        .map(|(x, y, pixel)| pixel.0[pattern.channel(x as i32, y as i32)]);
 
-    gray
+    GrayImage::from_vec(img.width(), img.height(), bayer_gray_raw.collect())
+        .expect("Fail to convert RGB image to Gray.")
 }
 
-trait ExtIndexTrait<const D: usize> {
+trait ExtIndexTrait<T, const D: usize> {
     /// Get an extended index value at coordinates (x, y).
-    fn ext_index(&self, x: i32, y: i32) -> [u8; D];
-    /// Convert out-of-bounds indices to valid in-bounds indices.
+    fn ext_index(&self, x: i32, y: i32) -> [T; D];
+    /// Convert out-of-bounds indices to valid in-bounds indices, mirroring
+    /// around the border pixels: -1 becomes 1 and `width` becomes
+    /// `width - 2`. Mirroring keeps the parity, so the colour of a Bayer
+    /// pattern at the converted index is the same.
+    ///
+    /// Indices that are still out of bounds after mirroring, e.g. two pixels
+    /// out of an image two pixels wide, repeat the first two pixels instead,
+    /// which keeps the parity too. Panic if the image is empty.
     fn convert_index(&self, x: i32, y: i32, width: u32, height: u32) -> (u32, u32) {
-        let (width_i32, height_i32) = (width as i32, height as i32);
-        let (right_bound, bottom_bound) = ((width - 1) as i32, (height - 1) as i32);
-
-        let (actual_x, actual_y) = if x == -1 && y == -1 {
-            // top-left edge
-            (1, 1)
-        } else if x == width_i32 && y == -1 {
-            // top-right edge
-            (width - 2, 1)
-        } else if x == width_i32 && y == height_i32 {
-            // bottom-right edge
-            (width - 2, height - 2)
-        } else if x == -1 && y == height_i32 {
-            // bottom-left edge
-            (1, height - 2)
-        } else if y == -1 {
-            // top-line edge
-            (x as u32, 1)
-        } else if y == height_i32 {
-            // bottom-line edge
-            (x as u32, height - 2)
-        } else if x == -1 {
-            // left-line edge
-            (1, y as u32)
-        } else if x == width_i32 {
-            // right-line edge
-            (width - 2, y as u32)
-        } else if (0..=right_bound).contains(&x) && (0..=bottom_bound).contains(&y) {
-            // on image
-            (x as u32, y as u32)
-        } else {
-            panic!("Index error: index range should be [-1, width] x [-1, height], current index is {{x: {}, y: {}}}", x, y);
+        let mirror = |i: i32, size: u32| {
+            let last = size as i32 - 1;
+            let i = if i < 0 {
+                -i
+            } else if i > last {
+                2 * last - i
+            } else {
+                i
+            };
+            let i = if (0..=last).contains(&i) {
+                i
+            } else {
+                i.rem_euclid(2).min(last)
+            };
+            (0..=last).contains(&i).then_some(i as u32)
         };
 
-        (actual_x, actual_y)
+        match (mirror(x, width), mirror(y, height)) {
+            (Some(actual_x), Some(actual_y)) => (actual_x, actual_y),
+            _ => panic!(
+                "Index error: the image is empty, current index is {{x: {}, y: {}}}",
+                x, y
+            ),
+        }
     }
 }
 
-impl ExtIndexTrait<3> for RgbImage {
+impl ExtIndexTrait<u8, 3> for RgbImage {
     fn ext_index(&self, x: i32, y: i32) -> [u8; 3] {
         let (width, height) = self.dimensions();
         let (actual_x, actual_y) = self.convert_index(x, y, width, height);
@@ -70,7 +100,7 @@ impl ExtIndexTrait<3> for RgbImage {
     }
 }
 
-impl ExtIndexTrait<1> for GrayImage {
+impl ExtIndexTrait<u8, 1> for GrayImage {
     fn ext_index(&self, x: i32, y: i32) -> [u8; 1] {
         let (width, height) = self.dimensions();
         let (actual_x, actual_y) = self.convert_index(x, y, width, height);
@@ -79,238 +109,668 @@ impl ExtIndexTrait<1> for GrayImage {
     }
 }
 
-/// There are a lot of demosaic algorithms. Here is just a simplified one.
-pub fn demosaic(img: &GrayImage) -> RgbImage {
+impl ExtIndexTrait<f32, 3> for Rgb32FImage {
+    fn ext_index(&self, x: i32, y: i32) -> [f32; 3] {
+        let (width, height) = self.dimensions();
+        let (actual_x, actual_y) = self.convert_index(x, y, width, height);
+
+        unsafe { self.unsafe_get_pixel(actual_x, actual_y).0 }
+    }
+}
+
+/// Keep the sampled channel, and fill green elsewhere with the average of
+/// the four neighbours, which are all green in a Bayer pattern.
+fn fill_green(img: &GrayImage, pattern: CfaPattern, x: i32, y: i32) -> Rgb<u8> {
+    let mut pixel = [0; 3];
+    let channel = pattern.channel(x, y);
+    pixel[channel] = unsafe { img.unsafe_get_pixel(x as u32, y as u32).0[0] };
+
+    if channel != GREEN {
+        let left = img.ext_index(x - 1, y)[0];
+        let right = img.ext_index(x + 1, y)[0];
+        let up = img.ext_index(x, y - 1)[0];
+        let down = img.ext_index(x, y + 1)[0];
+
+        pixel[GREEN] = ((left as u16 + right as u16 + up as u16 + down as u16) / 4) as u8;
+    }
+
+    Rgb(pixel)
+}
+
+/// Added to both sides of the colour to green ratio, so that it stays
+/// finite where green is zero.
+const RATIO_OFFSET: f32 = 1.0;
+
+/// Interpolate the ratio of `channel` to green over the neighbours, and
+/// scale it back by the green at the current pixel.
+///
+/// The ratio is taken between the values plus `RATIO_OFFSET`, so a black
+/// green gives a ratio of at most 256 instead of infinity or NaN, and black
+/// and white areas stay exactly black and white. The result is rounded and
+/// saturated to `0..=255`.
+fn ratio_interpolate(
+    rgb_image: &RgbImage,
+    neighbours: &[(i32, i32)],
+    channel: usize,
+    green: u8,
+) -> u8 {
+    let sum: f32 = neighbours
+        .iter()
+        .map(|&(x, y)| {
+            let pixel = rgb_image.ext_index(x, y);
+            (pixel[channel] as f32 + RATIO_OFFSET) / (pixel[GREEN] as f32 + RATIO_OFFSET)
+        })
+        .sum();
+    let value = sum / neighbours.len() as f32 * (green as f32 + RATIO_OFFSET) - RATIO_OFFSET;
+
+    value.round().clamp(0.0, 255.0) as u8
+}
+
+/// Fill red and blue from the output of `fill_green`. Only the channels
+/// sampled by the sensor and green are read, so this can run in place.
+fn fill_red_blue(rgb_image: &RgbImage, pattern: CfaPattern, x: i32, y: i32) -> Rgb<u8> {
+    let mut pixel = rgb_image.ext_index(x, y);
+    let green = pixel[GREEN];
+
+    match pattern.channel(x, y) {
+        GREEN => {
+            // one colour is on the same row, the other on the same column
+            let horizontal = pattern.channel(x + 1, y);
+            let vertical = RED + BLUE - horizontal;
+            pixel[horizontal] =
+                ratio_interpolate(rgb_image, &[(x - 1, y), (x + 1, y)], horizontal, green);
+            pixel[vertical] =
+                ratio_interpolate(rgb_image, &[(x, y - 1), (x, y + 1)], vertical, green);
+        }
+        channel => {
+            // the other colour is on the diagonals
+            let other = RED + BLUE - channel;
+            let diagonals = [
+                (x - 1, y - 1),
+                (x + 1, y - 1),
+                (x - 1, y + 1),
+                (x + 1, y + 1),
+            ];
+            pixel[other] = ratio_interpolate(rgb_image, &diagonals, other, green);
+        }
+    }
+
+    Rgb(pixel)
+}
+
+/// There are a lot of demosaic algorithms. Here is just a simplified one:
+/// green is interpolated bilinearly, then red and blue through their ratio
+/// to green, see `ratio_interpolate` for how zero green is handled.
+pub fn demosaic(img: &GrayImage, pattern: CfaPattern) -> RgbImage {
     let (width, height) = img.dimensions();
     let (width_i32, height_i32) = (width as i32, height as i32);
     let mut rgb_image = RgbImage::new(width, height);
 
     for y in 0..height_i32 {
         for x in 0..width_i32 {
-            let red = if (y % 2 == 1) && (x % 2 == 0) {
-                unsafe { img.unsafe_get_pixel(x as u32, y as u32).0[0] }
-            } else {
-                0
-            };
-
-            let blue = if (y % 2 == 0) && (x % 2 == 1) {
-                unsafe { img.unsafe_get_pixel(x as u32, y as u32).0[0] }
-            } else {
-                0
-            };
-
-            let green = if (y % 2 == 0) && (x % 2 == 0) || (y % 2 == 1) && (x % 2 == 1) {
-                unsafe { img.unsafe_get_pixel(x as u32, y as u32).0[0] }
-            } else {
-                let left = img.ext_index(x - 1, y)[0];
-                let right = img.ext_index(x + 1, y)[0];
-                let up = img.ext_index(x, y - 1)[0];
-                let down = img.ext_index(x, y + 1)[0];
-
-                ((left as u16 + right as u16 + up as u16 + down as u16) / 4) as u8
-            };
-
-            rgb_image.put_pixel(x as u32, y as u32, Rgb([red, green, blue]));
+            rgb_image.put_pixel(x as u32, y as u32, fill_green(img, pattern, x, y));
         }
     }
 
-    for y in 0..(height_i32) {
-        for x in 0..(width_i32) {
-            let (x_mod, y_mod) = (x % 2, y % 2);
-            match (x_mod, y_mod) {
-                // origin green only
-                (0, 0) | (1, 1) => {
-                    let left = rgb_image.ext_index(x - 1, y);
-                    let right = rgb_image.ext_index(x + 1, y);
-                    let up = rgb_image.ext_index(x, y - 1);
-                    let down = rgb_image.ext_index(x, y + 1);
-
-                    let green = rgb_image.ext_index(x, y)[1];
-
-                    let (blue, red) = if x_mod == 0 && y_mod == 0 {
-                        // orgin top-left green only
-                        let blue = ((left[2] as f32 / left[1] as f32
-                            + right[2] as f32 / right[1] as f32)
-                            / 2.0
-                            * green as f32) as u8;
-                        let red = ((up[0] as f32 / up[1] as f32 + down[0] as f32 / down[1] as f32)
-                            / 2.0
-                            * green as f32) as u8;
-
-                        (blue, red)
-                    } else {
-                        // orgin bottom-right green only
-                        let blue = ((up[2] as f32 / up[1] as f32 + down[2] as f32 / down[1] as f32)
-                            / 2.0
-                            * green as f32) as u8;
-                        let red = ((left[0] as f32 / left[1] as f32
-                            + right[0] as f32 / right[1] as f32)
-                            / 2.0
-                            * green as f32) as u8;
-
-                        (blue, red)
-                    };
-
-                    rgb_image.put_pixel(x as u32, y as u32, Rgb([red, green, blue]));
-                }
-                (1, 0) | (0, 1) => {
-                    let up_left = rgb_image.ext_index(x - 1, y - 1);
-                    let up_right = rgb_image.ext_index(x + 1, y - 1);
-                    let down_left = rgb_image.ext_index(x - 1, y + 1);
-                    let down_right = rgb_image.ext_index(x + 1, y + 1);
-
-                    let green = rgb_image.ext_index(x, y)[1];
-
-                    let (blue, red) = if x_mod == 1 {
-                        // origin blue only
-                        let blue = rgb_image.ext_index(x, y)[2];
-                        let red = ((up_left[0] as f32 / up_left[1] as f32
-                            + up_right[0] as f32 / up_right[1] as f32
-                            + down_left[0] as f32 / down_left[1] as f32
-                            + down_right[0] as f32 / down_right[1] as f32)
-                            / 4.0
-                            * green as f32) as u8;
-
-                        (blue, red)
-                    } else {
-                        // origin red only
-                        let red = rgb_image.ext_index(x, y)[0];
-                        let blue = ((up_left[2] as f32 / up_left[1] as f32
-                            + up_right[2] as f32 / up_right[1] as f32
-                            + down_left[2] as f32 / down_left[1] as f32
-                            + down_right[2] as f32 / down_right[1] as f32)
-                            / 4.0
-                            * green as f32) as u8;
-
-                        (blue, red)
-                    };
-
-                    rgb_image.put_pixel(x as u32, y as u32, Rgb([red, green, blue]));
-                }
-                _ => unreachable!(),
-            }
+    for y in 0..height_i32 {
+        for x in 0..width_i32 {
+            let pixel = fill_red_blue(&rgb_image, pattern, x, y);
+            rgb_image.put_pixel(x as u32, y as u32, pixel);
         }
     }
 
     rgb_image
 }
 
-pub fn demosaic_rayon(img: &GrayImage) -> RgbImage {
+pub fn demosaic_rayon(img: &GrayImage, pattern: CfaPattern) -> RgbImage {
     let (width, height) = img.dimensions();
     let mut rgb_image = RgbImage::new(width, height);
 
     rgb_image
         .par_enumerate_pixels_mut()
         .for_each(|(x, y, pixel)| {
-            let x = x as i32;
-            let y = y as i32;
+            *pixel = fill_green(img, pattern, x as i32, y as i32);
+        });
 
-            let red = if (y % 2 == 1) && (x % 2 == 0) {
-                unsafe { img.unsafe_get_pixel(x as u32, y as u32).0[0] }
-            } else {
-                0
-            };
+    RgbImage::from_par_fn(width, height, |x, y| {
+        fill_red_blue(&rgb_image, pattern, x as i32, y as i32)
+    })
+}
 
-            let blue = if (y % 2 == 0) && (x % 2 == 1) {
-                unsafe { img.unsafe_get_pixel(x as u32, y as u32).0[0] }
-            } else {
-                0
-            };
+/// The average of the mosaic over the given pixels, rounded to nearest.
+fn average(img: &GrayImage, pixels: &[(i32, i32)]) -> u8 {
+    let sum: u32 = pixels
+        .iter()
+        .map(|&(x, y)| img.ext_index(x, y)[0] as u32)
+        .sum();
+    let count = pixels.len() as u32;
 
-            let green = if (y % 2 == 0) && (x % 2 == 0) || (y % 2 == 1) && (x % 2 == 1) {
-                unsafe { img.unsafe_get_pixel(x as u32, y as u32).0[0] }
-            } else {
-                let left = img.ext_index(x - 1, y)[0];
-                let right = img.ext_index(x + 1, y)[0];
-                let up = img.ext_index(x, y - 1)[0];
-                let down = img.ext_index(x, y + 1)[0];
+    ((sum + count / 2) / count) as u8
+}
 
-                ((left as u16 + right as u16 + up as u16 + down as u16) / 4) as u8
-            };
+/// Fill each missing channel with the average of the nearest pixels that
+/// sampled it.
+fn bilinear_pixel(img: &GrayImage, pattern: CfaPattern, x: i32, y: i32) -> Rgb<u8> {
+    let mut pixel = [0; 3];
+    let channel = pattern.channel(x, y);
+    pixel[channel] = img.ext_index(x, y)[0];
+
+    let horizontal = [(x - 1, y), (x + 1, y)];
+    let vertical = [(x, y - 1), (x, y + 1)];
+    if channel == GREEN {
+        let horizontal_channel = pattern.channel(x + 1, y);
+        pixel[horizontal_channel] = average(img, &horizontal);
+        pixel[RED + BLUE - horizontal_channel] = average(img, &vertical);
+    } else {
+        let cross = [horizontal, vertical].concat();
+        let diagonals = [
+            (x - 1, y - 1),
+            (x + 1, y - 1),
+            (x - 1, y + 1),
+            (x + 1, y + 1),
+        ];
+        pixel[GREEN] = average(img, &cross);
+        pixel[RED + BLUE - channel] = average(img, &diagonals);
+    }
+
+    Rgb(pixel)
+}
+
+/// Plain bilinear interpolation, as a baseline for the other methods.
+pub fn demosaic_bilinear(img: &GrayImage, pattern: CfaPattern) -> RgbImage {
+    bilinear(img, pattern, false)
+}
+
+pub fn demosaic_bilinear_rayon(img: &GrayImage, pattern: CfaPattern) -> RgbImage {
+    bilinear(img, pattern, true)
+}
+
+fn bilinear(img: &GrayImage, pattern: CfaPattern, parallel: bool) -> RgbImage {
+    let (width, height) = img.dimensions();
+    build(width, height, parallel, |x, y| {
+        bilinear_pixel(img, pattern, x as i32, y as i32)
+    })
+}
+
+/// Build an image pixel by pixel, on the rayon thread pool if `parallel`.
+fn build<P, F>(width: u32, height: u32, parallel: bool, f: F) -> ImageBuffer<P, Vec<P::Subpixel>>
+where
+    P: Pixel + Send + Sync,
+    P::Subpixel: Send + Sync,
+    F: Fn(u32, u32) -> P + Send + Sync,
+{
+    if parallel {
+        ImageBuffer::from_par_fn(width, height, f)
+    } else {
+        ImageBuffer::from_fn(width, height, f)
+    }
+}
+
+/// A filter tap: the offset from the current pixel and the weight in
+/// sixteenths.
+type Tap = (i32, i32, i32);
+
+/// Green at a red or blue pixel.
+const MALVAR_GREEN: [Tap; 9] = [
+    (0, 0, 8),
+    (-1, 0, 4),
+    (1, 0, 4),
+    (0, -1, 4),
+    (0, 1, 4),
+    (-2, 0, -2),
+    (2, 0, -2),
+    (0, -2, -2),
+    (0, 2, -2),
+];
+
+/// Red or blue at a green pixel, when that colour is on the same row.
+const MALVAR_ROW: [Tap; 11] = [
+    (0, 0, 10),
+    (-1, 0, 8),
+    (1, 0, 8),
+    (-2, 0, -2),
+    (2, 0, -2),
+    (-1, -1, -2),
+    (1, -1, -2),
+    (-1, 1, -2),
+    (1, 1, -2),
+    (0, -2, 1),
+    (0, 2, 1),
+];
+
+/// Red or blue at a green pixel, when that colour is on the same column.
+const MALVAR_COLUMN: [Tap; 11] = [
+    (0, 0, 10),
+    (0, -1, 8),
+    (0, 1, 8),
+    (0, -2, -2),
+    (0, 2, -2),
+    (-1, -1, -2),
+    (1, -1, -2),
+    (-1, 1, -2),
+    (1, 1, -2),
+    (-2, 0, 1),
+    (2, 0, 1),
+];
+
+/// Red at a blue pixel or blue at a red pixel.
+const MALVAR_DIAGONAL: [Tap; 9] = [
+    (0, 0, 12),
+    (-1, -1, 4),
+    (1, -1, 4),
+    (-1, 1, 4),
+    (1, 1, 4),
+    (-2, 0, -3),
+    (2, 0, -3),
+    (0, -2, -3),
+    (0, 2, -3),
+];
+
+fn convolve(img: &GrayImage, x: i32, y: i32, taps: &[Tap]) -> u8 {
+    let sum: i32 = taps
+        .iter()
+        .map(|&(dx, dy, weight)| img.ext_index(x + dx, y + dy)[0] as i32 * weight)
+        .sum();
+
+    ((sum + 8).div_euclid(16)).clamp(0, 255) as u8
+}
+
+fn malvar_pixel(img: &GrayImage, pattern: CfaPattern, x: i32, y: i32) -> Rgb<u8> {
+    let mut pixel = [0; 3];
+    let channel = pattern.channel(x, y);
+    pixel[channel] = img.ext_index(x, y)[0];
+
+    if channel == GREEN {
+        let horizontal = pattern.channel(x + 1, y);
+        pixel[horizontal] = convolve(img, x, y, &MALVAR_ROW);
+        pixel[RED + BLUE - horizontal] = convolve(img, x, y, &MALVAR_COLUMN);
+    } else {
+        pixel[GREEN] = convolve(img, x, y, &MALVAR_GREEN);
+        pixel[RED + BLUE - channel] = convolve(img, x, y, &MALVAR_DIAGONAL);
+    }
+
+    Rgb(pixel)
+}
+
+/// Bilinear interpolation corrected by the Laplacian of the sampled
+/// channel, which follows edges much better than plain bilinear at the same
+/// cost: a fixed 5x5 filter per missing channel.
+///
+/// Malvar, He and Cutler, "High-Quality Linear Interpolation for
+/// Demosaicing of Bayer-Patterned Color Images", 2004.
+pub fn demosaic_malvar(img: &GrayImage, pattern: CfaPattern) -> RgbImage {
+    malvar(img, pattern, false)
+}
+
+pub fn demosaic_malvar_rayon(img: &GrayImage, pattern: CfaPattern) -> RgbImage {
+    malvar(img, pattern, true)
+}
+
+fn malvar(img: &GrayImage, pattern: CfaPattern, parallel: bool) -> RgbImage {
+    let (width, height) = img.dimensions();
+    build(width, height, parallel, |x, y| {
+        malvar_pixel(img, pattern, x as i32, y as i32)
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+/// Green at (x, y) interpolated along `axis`: the average of the two green
+/// neighbours, corrected by the Laplacian of the sampled colour. Also
+/// return the gradient along `axis`, which is low when the interpolation
+/// does not cross an edge.
+fn directional_green(img: &GrayImage, x: i32, y: i32, axis: Axis) -> (u8, i32) {
+    let (dx, dy) = match axis {
+        Axis::Horizontal => (1, 0),
+        Axis::Vertical => (0, 1),
+    };
+    let at = |k: i32| img.ext_index(x + k * dx, y + k * dy)[0] as i32;
+
+    let laplacian = 2 * at(0) - at(-2) - at(2);
+    let green = (2 * (at(-1) + at(1)) + laplacian + 2).div_euclid(4);
+    let gradient = (at(-1) - at(1)).abs() + laplacian.abs();
+
+    (green.clamp(0, 255) as u8, gradient)
+}
+
+/// Hamilton and Adams: interpolate green along the axis with the lower
+/// gradient, or average both when they are equal.
+fn hamilton_adams_green(img: &GrayImage, pattern: CfaPattern, x: i32, y: i32) -> u8 {
+    if pattern.channel(x, y) == GREEN {
+        return img.ext_index(x, y)[0];
+    }
+
+    let (horizontal, horizontal_gradient) = directional_green(img, x, y, Axis::Horizontal);
+    let (vertical, vertical_gradient) = directional_green(img, x, y, Axis::Vertical);
+    match horizontal_gradient.cmp(&vertical_gradient) {
+        Ordering::Less => horizontal,
+        Ordering::Greater => vertical,
+        Ordering::Equal => (horizontal as u16 + vertical as u16).div_ceil(2) as u8,
+    }
+}
+
+/// Fill red and blue once green is known everywhere, by interpolating the
+/// difference to green bilinearly.
+fn color_difference_pixel(
+    img: &GrayImage,
+    green: &GrayImage,
+    pattern: CfaPattern,
+    x: i32,
+    y: i32,
+) -> Rgb<u8> {
+    let mut pixel = [0; 3];
+    let g = green.ext_index(x, y)[0] as i32;
+    pixel[GREEN] = g as u8;
+
+    let difference = |pixels: &[(i32, i32)]| {
+        let sum: i32 = pixels
+            .iter()
+            .map(|&(x, y)| img.ext_index(x, y)[0] as i32 - green.ext_index(x, y)[0] as i32)
+            .sum();
+        let count = pixels.len() as i32;
+        (g + (sum + count / 2).div_euclid(count)).clamp(0, 255) as u8
+    };
+
+    let channel = pattern.channel(x, y);
+    if channel == GREEN {
+        let horizontal = pattern.channel(x + 1, y);
+        pixel[horizontal] = difference(&[(x - 1, y), (x + 1, y)]);
+        pixel[RED + BLUE - horizontal] = difference(&[(x, y - 1), (x, y + 1)]);
+    } else {
+        pixel[channel] = img.ext_index(x, y)[0];
+        pixel[RED + BLUE - channel] = difference(&[
+            (x - 1, y - 1),
+            (x + 1, y - 1),
+            (x - 1, y + 1),
+            (x + 1, y + 1),
+        ]);
+    }
+
+    Rgb(pixel)
+}
+
+fn hamilton_adams(img: &GrayImage, pattern: CfaPattern, parallel: bool) -> RgbImage {
+    let (width, height) = img.dimensions();
+    let green: GrayImage = build(width, height, parallel, |x, y| {
+        Luma([hamilton_adams_green(img, pattern, x as i32, y as i32)])
+    });
+
+    build(width, height, parallel, |x, y| {
+        color_difference_pixel(img, &green, pattern, x as i32, y as i32)
+    })
+}
+
+/// Green is interpolated along edges instead of across them, then red and
+/// blue follow through the colour differences.
+///
+/// Hamilton and Adams, "Adaptive color plan interpolation in single sensor
+/// color electronic camera", US patent 5629734, 1997.
+pub fn demosaic_hamilton_adams(img: &GrayImage, pattern: CfaPattern) -> RgbImage {
+    hamilton_adams(img, pattern, false)
+}
+
+pub fn demosaic_hamilton_adams_rayon(img: &GrayImage, pattern: CfaPattern) -> RgbImage {
+    hamilton_adams(img, pattern, true)
+}
+
+/// CIELab of an sRGB pixel, stored in the channels of an `Rgb<f32>`.
+fn to_lab(pixel: &Rgb<u8>, rgb_to_xyz: &Matrix<f32>) -> Rgb<f32> {
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let xyz = rgb_to_xyz * Vector::new(linear(pixel[0]), linear(pixel[1]), linear(pixel[2]));
+
+    let f = |t: f32| {
+        const DELTA: f32 = 6.0 / 29.0;
+        if t > DELTA * DELTA * DELTA {
+            t.cbrt()
+        } else {
+            t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+        }
+    };
+    // relative to the D65 white point
+    let (fx, fy, fz) = (f(xyz.x / 0.95047), f(xyz.y), f(xyz.z / 1.08883));
+
+    Rgb([116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)])
+}
+
+/// For both candidates, how many of the four neighbours are close in
+/// lightness and colour. Close means no further than the neighbours along
+/// the interpolation axis of the better candidate.
+fn homogeneity(
+    lab_horizontal: &Rgb32FImage,
+    lab_vertical: &Rgb32FImage,
+    x: i32,
+    y: i32,
+) -> Rgb<u8> {
+    let neighbours = [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)];
+    let differences = |lab: &Rgb32FImage| {
+        let [l, a, b] = lab.ext_index(x, y);
+        neighbours.map(|(x, y)| {
+            let [nl, na, nb] = lab.ext_index(x, y);
+            ((l - nl).abs(), (a - na).powi(2) + (b - nb).powi(2))
+        })
+    };
+    let horizontal = differences(lab_horizontal);
+    let vertical = differences(lab_vertical);
 
-            *pixel = Rgb([red, green, blue]);
+    let lightness_epsilon = horizontal[0]
+        .0
+        .max(horizontal[1].0)
+        .min(vertical[2].0.max(vertical[3].0));
+    let color_epsilon = horizontal[0]
+        .1
+        .max(horizontal[1].1)
+        .min(vertical[2].1.max(vertical[3].1));
+    let count = |differences: [(f32, f32); 4]| {
+        differences
+            .iter()
+            .filter(|&&(l, c)| l <= lightness_epsilon && c <= color_epsilon)
+            .count() as u8
+    };
+
+    Rgb([count(horizontal), count(vertical), 0])
+}
+
+fn ahd(img: &GrayImage, pattern: CfaPattern, parallel: bool) -> RgbImage {
+    let (width, height) = img.dimensions();
+    let candidate = |axis| -> RgbImage {
+        let green: GrayImage = build(width, height, parallel, |x, y| {
+            let (x, y) = (x as i32, y as i32);
+            if pattern.channel(x, y) == GREEN {
+                Luma(img.ext_index(x, y))
+            } else {
+                Luma([directional_green(img, x, y, axis).0])
+            }
         });
+        build(width, height, parallel, |x, y| {
+            color_difference_pixel(img, &green, pattern, x as i32, y as i32)
+        })
+    };
+    let horizontal = candidate(Axis::Horizontal);
+    let vertical = candidate(Axis::Vertical);
 
-    RgbImage::from_par_fn(width, height, |x, y| {
-        let x = x as i32;
-        let y = y as i32;
-        let (x_mod, y_mod) = (x % 2, y % 2);
-        let green = rgb_image.ext_index(x, y)[1];
-
-        let (blue, red) = match (x_mod, y_mod) {
-            // origin green only
-            (0, 0) | (1, 1) => {
-                let left = rgb_image.ext_index(x - 1, y);
-                let right = rgb_image.ext_index(x + 1, y);
-                let up = rgb_image.ext_index(x, y - 1);
-                let down = rgb_image.ext_index(x, y + 1);
-
-                if x_mod == 0 && y_mod == 0 {
-                    // orgin top-left green only
-                    let blue = ((left[2] as f32 / left[1] as f32
-                        + right[2] as f32 / right[1] as f32)
-                        / 2.0
-                        * green as f32) as u8;
-                    let red = ((up[0] as f32 / up[1] as f32 + down[0] as f32 / down[1] as f32)
-                        / 2.0
-                        * green as f32) as u8;
-
-                    (blue, red)
-                } else {
-                    // orgin bottom-right green only
-                    let blue = ((up[2] as f32 / up[1] as f32 + down[2] as f32 / down[1] as f32)
-                        / 2.0
-                        * green as f32) as u8;
-                    let red = ((left[0] as f32 / left[1] as f32
-                        + right[0] as f32 / right[1] as f32)
-                        / 2.0
-                        * green as f32) as u8;
-
-                    (blue, red)
-                }
+    // sRGB to XYZ
+    let rgb_to_xyz = Matrix::from_rows([
+        [0.4124, 0.3576, 0.1805],
+        [0.2126, 0.7152, 0.0722],
+        [0.0193, 0.1192, 0.9505],
+    ]);
+    let lab = |rgb: &RgbImage| -> Rgb32FImage {
+        build(width, height, parallel, |x, y| {
+            to_lab(rgb.get_pixel(x, y), &rgb_to_xyz)
+        })
+    };
+    let (lab_horizontal, lab_vertical) = (lab(&horizontal), lab(&vertical));
+    let homogeneity_map: RgbImage = build(width, height, parallel, |x, y| {
+        homogeneity(&lab_horizontal, &lab_vertical, x as i32, y as i32)
+    });
+
+    // pick the candidate that is the most homogeneous around each pixel
+    build(width, height, parallel, |x, y| {
+        let (mut score_horizontal, mut score_vertical) = (0, 0);
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let [h, v, _] = homogeneity_map.ext_index(x as i32 + dx, y as i32 + dy);
+                score_horizontal += h as u32;
+                score_vertical += v as u32;
             }
-            (1, 0) | (0, 1) => {
-                let up_left = rgb_image.ext_index(x - 1, y - 1);
-                let up_right = rgb_image.ext_index(x + 1, y - 1);
-                let down_left = rgb_image.ext_index(x - 1, y + 1);
-                let down_right = rgb_image.ext_index(x + 1, y + 1);
-
-                if x_mod == 1 {
-                    // origin blue only
-                    let blue = rgb_image.ext_index(x, y)[2];
-                    let red = ((up_left[0] as f32 / up_left[1] as f32
-                        + up_right[0] as f32 / up_right[1] as f32
-                        + down_left[0] as f32 / down_left[1] as f32
-                        + down_right[0] as f32 / down_right[1] as f32)
-                        / 4.0
-                        * green as f32) as u8;
-
-                    (blue, red)
-                } else {
-                    // origin red only
-                    let red = rgb_image.ext_index(x, y)[0];
-                    let blue = ((up_left[2] as f32 / up_left[1] as f32
-                        + up_right[2] as f32 / up_right[1] as f32
-                        + down_left[2] as f32 / down_left[1] as f32
-                        + down_right[2] as f32 / down_right[1] as f32)
-                        / 4.0
-                        * green as f32) as u8;
-
-                    (blue, red)
-                }
+        }
+
+        let (h, v) = (horizontal.get_pixel(x, y), vertical.get_pixel(x, y));
+        match score_horizontal.cmp(&score_vertical) {
+            Ordering::Greater => *h,
+            Ordering::Less => *v,
+            Ordering::Equal => {
+                Rgb([0, 1, 2].map(|c| (h[c] as u16 + v[c] as u16).div_ceil(2) as u8))
             }
-            _ => unreachable!(),
+        }
+    })
+}
+
+/// Adaptive homogeneity-directed demosaicing: interpolate the whole image
+/// once along rows and once along columns, and keep for each pixel the one
+/// whose neighbourhood is the most homogeneous in CIELab. The median filter
+/// of the original paper is left out.
+///
+/// Hirakawa and Parks, "Adaptive Homogeneity-Directed Demosaicing
+/// Algorithm", 2005.
+pub fn demosaic_ahd(img: &GrayImage, pattern: CfaPattern) -> RgbImage {
+    ahd(img, pattern, false)
+}
+
+pub fn demosaic_ahd_rayon(img: &GrayImage, pattern: CfaPattern) -> RgbImage {
+    ahd(img, pattern, true)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DemosaicMethod {
+    /// See `demosaic`.
+    ColorRatio,
+    /// See `demosaic_bilinear`.
+    Bilinear,
+    /// See `demosaic_malvar`.
+    Malvar,
+    /// See `demosaic_hamilton_adams`.
+    HamiltonAdams,
+    /// See `demosaic_ahd`.
+    Ahd,
+}
+
+impl DemosaicMethod {
+    pub const ALL: [DemosaicMethod; 5] = [
+        DemosaicMethod::ColorRatio,
+        DemosaicMethod::Bilinear,
+        DemosaicMethod::Malvar,
+        DemosaicMethod::HamiltonAdams,
+        DemosaicMethod::Ahd,
+    ];
+}
+
+impl Display for DemosaicMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DemosaicMethod::ColorRatio => "color ratio",
+            DemosaicMethod::Bilinear => "bilinear",
+            DemosaicMethod::Malvar => "Malvar-He-Cutler",
+            DemosaicMethod::HamiltonAdams => "Hamilton-Adams",
+            DemosaicMethod::Ahd => "AHD",
         };
+        f.pad(name)
+    }
+}
 
-        Rgb([red, green, blue])
-    })
+/// Demosaic with any of the methods, using their rayon versions.
+pub fn demosaic_with(img: &GrayImage, pattern: CfaPattern, method: DemosaicMethod) -> RgbImage {
+    match method {
+        DemosaicMethod::ColorRatio => demosaic_rayon(img, pattern),
+        DemosaicMethod::Bilinear => demosaic_bilinear_rayon(img, pattern),
+        DemosaicMethod::Malvar => demosaic_malvar_rayon(img, pattern),
+        DemosaicMethod::HamiltonAdams => demosaic_hamilton_adams_rayon(img, pattern),
+        DemosaicMethod::Ahd => demosaic_ahd_rayon(img, pattern),
+    }
+}
+
+/// Peak signal-to-noise ratio in decibels over all channels. Infinite for
+/// identical images, which includes two empty ones.
+pub fn psnr(reference: &RgbImage, image: &RgbImage) -> f64 {
+    assert_eq!(
+        reference.dimensions(),
+        image.dimensions(),
+        "The images should have the same size."
+    );
+    let squared_error: f64 = reference
+        .as_raw()
+        .iter()
+        .zip(image.as_raw())
+        .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+        .sum();
+    if squared_error == 0.0 {
+        return f64::INFINITY;
+    }
+    let mse = squared_error / reference.as_raw().len() as f64;
+
+    10.0 * (255.0 * 255.0 / mse).log10()
+}
+
+/// The PSNR of every method on the same mosaic, see `psnr_report`.
+#[derive(Debug, Clone)]
+pub struct PsnrReport {
+    pub pattern: CfaPattern,
+    pub psnr: Vec<(DemosaicMethod, f64)>,
+}
+
+impl Display for PsnrReport {
+    /// One line per method, with the difference to the color ratio method.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let baseline = self
+            .psnr
+            .iter()
+            .find(|(method, _)| *method == DemosaicMethod::ColorRatio)
+            .map(|&(_, psnr)| psnr);
+
+        write!(f, "PSNR on a {:?} mosaic:", self.pattern)?;
+        for &(method, psnr) in &self.psnr {
+            write!(f, "\n  {:<16} {:6.2} dB", method, psnr)?;
+            if let Some(baseline) = baseline {
+                write!(f, " ({:+.2} dB)", psnr - baseline)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Mosaic `img` with `pattern`, demosaic it with every method and measure
+/// each result against `img`.
+pub fn psnr_report(img: &RgbImage, pattern: CfaPattern) -> PsnrReport {
+    let mosaic = cast_rgb_to_bayer_mosaic(img, pattern);
+    let psnr = DemosaicMethod::ALL
+        .iter()
+        .map(|&method| (method, psnr(img, &demosaic_with(&mosaic, pattern, method))))
+        .collect();
+
+    PsnrReport { pattern, psnr }
 }
 
 #[cfg(test)]
 mod test {
-    use image::DynamicImage;
+    use image::{DynamicImage, GrayImage, Luma, Rgb, RgbImage};
 
-    use super::ExtIndexTrait;
+    use super::{
+        cast_rgb_to_bayer_mosaic, demosaic, demosaic_ahd, demosaic_ahd_rayon, demosaic_bilinear,
+        demosaic_bilinear_rayon, demosaic_hamilton_adams, demosaic_hamilton_adams_rayon,
+        demosaic_malvar, demosaic_malvar_rayon, demosaic_rayon, psnr, psnr_report, CfaPattern,
+        DemosaicMethod, ExtIndexTrait,
+    };
 
     #[test]
     fn test_index() {
@@ -330,4 +790,262 @@ mod test {
         img.ext_index(3318, 4161);
         img.ext_index(50, 60);
     }
+
+    fn gradient(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            Rgb([
+                (40 + 3 * x) as u8,
+                (30 + 2 * x + 3 * y) as u8,
+                (200 - 4 * y) as u8,
+            ])
+        })
+    }
+
+    #[test]
+    fn test_cfa_pattern() {
+        let img = RgbImage::from_pixel(2, 2, Rgb([10, 20, 30]));
+        for (pattern, tile) in [
+            (CfaPattern::Rggb, [10, 20, 20, 30]),
+            (CfaPattern::Bggr, [30, 20, 20, 10]),
+            (CfaPattern::Grbg, [20, 10, 30, 20]),
+            (CfaPattern::Gbrg, [20, 30, 10, 20]),
+        ] {
+            assert_eq!(cast_rgb_to_bayer_mosaic(&img, pattern).into_raw(), tile);
+            assert_eq!(pattern.channel(-1, -1), pattern.channel(1, 1));
+        }
+    }
+
+    #[test]
+    fn test_demosaic_patterns() {
+        let img = gradient(12, 9);
+        for pattern in CfaPattern::ALL {
+            let mosaic = cast_rgb_to_bayer_mosaic(&img, pattern);
+            let rgb = demosaic(&mosaic, pattern);
+            assert_eq!(rgb, demosaic_rayon(&mosaic, pattern));
+
+            // the sampled values are kept, so mosaicing again is lossless
+            assert_eq!(cast_rgb_to_bayer_mosaic(&rgb, pattern), mosaic);
+            // and a smooth image is recovered closely away from the border
+            for (x, y, expected) in img.enumerate_pixels() {
+                if x == 0 || y == 0 || x == img.width() - 1 || y == img.height() - 1 {
+                    continue;
+                }
+                let actual = rgb.get_pixel(x, y);
+                for c in 0..3 {
+                    assert!((expected[c] as i32 - actual[c] as i32).abs() <= 6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_demosaic_bilinear() {
+        let img = gradient(12, 9);
+        for pattern in CfaPattern::ALL {
+            let mosaic = cast_rgb_to_bayer_mosaic(&img, pattern);
+            let rgb = demosaic_bilinear(&mosaic, pattern);
+            assert_eq!(rgb, demosaic_bilinear_rayon(&mosaic, pattern));
+            assert_eq!(cast_rgb_to_bayer_mosaic(&rgb, pattern), mosaic);
+
+            // a linear gradient is reproduced exactly away from the border
+            for (x, y, expected) in img.enumerate_pixels() {
+                if x == 0 || y == 0 || x == img.width() - 1 || y == img.height() - 1 {
+                    continue;
+                }
+                assert_eq!(rgb.get_pixel(x, y), expected);
+            }
+        }
+    }
+
+    /// Sharp edges with correlated channels, like text on paper.
+    fn document(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            let ink = (x / 3 + y / 5) % 2 == 0;
+            if ink {
+                Rgb([40, 30, 20])
+            } else {
+                Rgb([240, 230, 200])
+            }
+        })
+    }
+
+    fn squared_error(a: &RgbImage, b: &RgbImage) -> u64 {
+        a.as_raw()
+            .iter()
+            .zip(b.as_raw())
+            .map(|(&a, &b)| (a as i64 - b as i64).pow(2) as u64)
+            .sum()
+    }
+
+    #[test]
+    fn test_demosaic_malvar() {
+        let img = gradient(12, 9);
+        for pattern in CfaPattern::ALL {
+            let mosaic = cast_rgb_to_bayer_mosaic(&img, pattern);
+            let rgb = demosaic_malvar(&mosaic, pattern);
+            assert_eq!(rgb, demosaic_malvar_rayon(&mosaic, pattern));
+            assert_eq!(cast_rgb_to_bayer_mosaic(&rgb, pattern), mosaic);
+
+            for (x, y, expected) in img.enumerate_pixels() {
+                if x < 2 || y < 2 || x > img.width() - 3 || y > img.height() - 3 {
+                    continue;
+                }
+                assert_eq!(rgb.get_pixel(x, y), expected);
+            }
+        }
+
+        let img = document(32, 32);
+        let mosaic = cast_rgb_to_bayer_mosaic(&img, CfaPattern::Rggb);
+        let malvar = squared_error(&img, &demosaic_malvar(&mosaic, CfaPattern::Rggb));
+        let bilinear = squared_error(&img, &demosaic_bilinear(&mosaic, CfaPattern::Rggb));
+        assert!(malvar < bilinear);
+    }
+
+    #[test]
+    fn test_mirror_index() {
+        let img = GrayImage::from_fn(5, 4, |x, y| Luma([(10 * y + x) as u8]));
+        assert_eq!(img.ext_index(-1, -1), [11]);
+        assert_eq!(img.ext_index(5, 4), [23]);
+        assert_eq!(img.ext_index(-2, 2), [22]);
+        assert_eq!(img.ext_index(6, 5), [12]);
+
+        // too small to mirror, the parity is kept
+        let img = GrayImage::from_fn(2, 1, |x, y| Luma([(10 * y + x) as u8]));
+        assert_eq!(img.ext_index(-2, 1), [0]);
+        assert_eq!(img.ext_index(3, -2), [1]);
+        assert_eq!(img.ext_index(-7, 0), [1]);
+    }
+
+    #[test]
+    fn test_demosaic_small() {
+        for (width, height) in [(1, 1), (2, 2), (2, 4), (4, 2), (3, 2), (2, 5)] {
+            let img = gradient(width, height);
+            for pattern in CfaPattern::ALL {
+                let mosaic = cast_rgb_to_bayer_mosaic(&img, pattern);
+                for (rgb, rgb_rayon) in [
+                    (demosaic(&mosaic, pattern), demosaic_rayon(&mosaic, pattern)),
+                    (
+                        demosaic_bilinear(&mosaic, pattern),
+                        demosaic_bilinear_rayon(&mosaic, pattern),
+                    ),
+                    (
+                        demosaic_malvar(&mosaic, pattern),
+                        demosaic_malvar_rayon(&mosaic, pattern),
+                    ),
+                    (
+                        demosaic_hamilton_adams(&mosaic, pattern),
+                        demosaic_hamilton_adams_rayon(&mosaic, pattern),
+                    ),
+                    (
+                        demosaic_ahd(&mosaic, pattern),
+                        demosaic_ahd_rayon(&mosaic, pattern),
+                    ),
+                ] {
+                    assert_eq!(rgb.dimensions(), (width, height));
+                    assert_eq!(rgb, rgb_rayon);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_demosaic_edge_aware() {
+        let img = gradient(12, 9);
+        for pattern in CfaPattern::ALL {
+            let mosaic = cast_rgb_to_bayer_mosaic(&img, pattern);
+            for (rgb, rgb_rayon) in [
+                (
+                    demosaic_hamilton_adams(&mosaic, pattern),
+                    demosaic_hamilton_adams_rayon(&mosaic, pattern),
+                ),
+                (
+                    demosaic_ahd(&mosaic, pattern),
+                    demosaic_ahd_rayon(&mosaic, pattern),
+                ),
+            ] {
+                assert_eq!(rgb, rgb_rayon);
+                assert_eq!(cast_rgb_to_bayer_mosaic(&rgb, pattern), mosaic);
+
+                // green reaches two pixels out, then red and blue one more
+                for (x, y, expected) in img.enumerate_pixels() {
+                    if x < 3 || y < 3 || x > img.width() - 4 || y > img.height() - 4 {
+                        continue;
+                    }
+                    assert_eq!(rgb.get_pixel(x, y), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_psnr_report() {
+        let img = document(48, 40);
+        assert_eq!(psnr(&img, &img), f64::INFINITY);
+        let empty = RgbImage::new(0, 0);
+        assert_eq!(psnr(&empty, &empty), f64::INFINITY);
+
+        for pattern in CfaPattern::ALL {
+            let report = psnr_report(&img, pattern);
+            assert_eq!(report.psnr.len(), DemosaicMethod::ALL.len());
+
+            let psnr_of = |method| {
+                report
+                    .psnr
+                    .iter()
+                    .find(|(m, _)| *m == method)
+                    .map(|&(_, psnr)| psnr)
+                    .unwrap()
+            };
+            let baseline = psnr_of(DemosaicMethod::ColorRatio);
+            assert!(psnr_of(DemosaicMethod::HamiltonAdams) > baseline);
+            assert!(psnr_of(DemosaicMethod::Ahd) > baseline);
+        }
+
+        let report = psnr_report(&img, CfaPattern::Rggb).to_string();
+        assert!(report.starts_with("PSNR on a Rggb mosaic:\n  color ratio "));
+        assert!(report.contains("\n  AHD "));
+    }
+
+    #[test]
+    fn test_demosaic_extremes() {
+        for pattern in CfaPattern::ALL {
+            for color in [
+                [0, 0, 0],
+                [255, 255, 255],
+                // zero green used to give NaN and infinite ratios
+                [255, 0, 0],
+                [0, 0, 255],
+                [255, 0, 255],
+                // a ratio far above one with a dim green
+                [255, 1, 0],
+                [100, 150, 200],
+            ] {
+                let img = RgbImage::from_pixel(8, 6, Rgb(color));
+                let mosaic = cast_rgb_to_bayer_mosaic(&img, pattern);
+                assert_eq!(demosaic(&mosaic, pattern), img);
+                assert_eq!(demosaic_rayon(&mosaic, pattern), img);
+            }
+
+            // red next to white, where the ratio of red to green is large
+            let img = RgbImage::from_fn(8, 6, |x, _| {
+                if x < 4 {
+                    Rgb([255, 0, 0])
+                } else {
+                    Rgb([255, 255, 255])
+                }
+            });
+            let mosaic = cast_rgb_to_bayer_mosaic(&img, pattern);
+            let rgb = demosaic(&mosaic, pattern);
+            assert_eq!(rgb, demosaic_rayon(&mosaic, pattern));
+            for (x, _, pixel) in rgb.enumerate_pixels() {
+                match x {
+                    0..=1 => assert_eq!(pixel, &Rgb([255, 0, 0])),
+                    // red over green is up to 256 on the left, saturated by the clamp
+                    4 => assert_eq!(pixel[0], 255),
+                    5.. => assert_eq!(pixel, &Rgb([255, 255, 255])),
+                    _ => {}
+                }
+            }
+        }
+    }
 }
@@ -1 +1 @@
-pub mod bayer;
\ No newline at end of file
+pub mod bayer;
@@ -1,6 +1,6 @@
 use image::DynamicImage;
 use learn_computer_graphics_in_rust::image_processing::bayer::{
-    cast_rgb_to_bayer_mosaic, demosaic, demosaic_rayon,
+    cast_rgb_to_bayer_mosaic, demosaic_rayon, psnr_report, CfaPattern,
 };
 
 fn main() {
@@ -10,9 +10,11 @@ fn main() {
     } else {
         panic!("Cannot read the image correctly.")
     };
-    let gray = cast_rgb_to_bayer_mosaic(&img);
+    let gray = cast_rgb_to_bayer_mosaic(&img, CfaPattern::Gbrg);
     gray.save("./resources/scan_bayer_mosaic.png").unwrap();
 
-    let rgb = demosaic_rayon(&gray);
+    let rgb = demosaic_rayon(&gray, CfaPattern::Gbrg);
     rgb.save("./resources/scan_bayer_demosaic.png").unwrap();
+
+    println!("{}", psnr_report(&img, CfaPattern::Gbrg));
 }
@@ -0,0 +1,242 @@
+use std::fmt::Write;
+
+use super::{Direction, Matrix, Matrix4, Normal, Point, Quaternion, Real, Scalar};
+
+/// How far apart two numbers may be and still count as equal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tolerance<T = f32> {
+    /// `|a - b| <= epsilon`. Only meaningful if the scale is known.
+    Absolute(T),
+    /// `|a - b| <= max_relative * max(|a|, |b|)`. Relative error is useless
+    /// near zero, so values within `epsilon` are always equal.
+    Relative { epsilon: T, max_relative: T },
+    /// At most `max_ulps` representable numbers apart, or within `epsilon`
+    /// for the same reason as above.
+    Ulps { epsilon: T, max_ulps: u32 },
+}
+
+impl<T: Real> Tolerance<T> {
+    pub fn relative(max_relative: T) -> Self {
+        Tolerance::Relative {
+            epsilon: T::EPSILON,
+            max_relative,
+        }
+    }
+
+    pub fn ulps(max_ulps: u32) -> Self {
+        Tolerance::Ulps {
+            epsilon: T::EPSILON,
+            max_ulps,
+        }
+    }
+}
+
+/// Approximate equality, mostly for tests: floating point results rarely
+/// match exactly, and printing them is no substitute for a check.
+///
+/// Composite types compare component by component, so every component has
+/// to be within the tolerance.
+pub trait ApproxEq<T = f32> {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance<T>) -> bool;
+}
+
+macro_rules! impl_approx_float {
+    ($t: ty, $bits: ty) => {
+        impl ApproxEq<$t> for $t {
+            fn approx_eq(&self, other: &Self, tolerance: Tolerance<$t>) -> bool {
+                let (a, b) = (*self, *other);
+                // also covers infinities of the same sign
+                if a == b {
+                    return true;
+                }
+                // NaN, and infinities which would otherwise be one ULP from `MAX`
+                if !a.is_finite() || !b.is_finite() {
+                    return false;
+                }
+                let diff = (a - b).abs();
+
+                match tolerance {
+                    Tolerance::Absolute(epsilon) => diff <= epsilon,
+                    Tolerance::Relative {
+                        epsilon,
+                        max_relative,
+                    } => diff <= epsilon || diff <= a.abs().max(b.abs()) * max_relative,
+                    Tolerance::Ulps { epsilon, max_ulps } => {
+                        if diff <= epsilon {
+                            true
+                        } else if a.is_sign_negative() != b.is_sign_negative() {
+                            false
+                        } else {
+                            // floats of the same sign are ordered like their bits
+                            let ulps = (a.to_bits() as $bits).abs_diff(b.to_bits() as $bits);
+                            ulps <= max_ulps as _
+                        }
+                    }
+                }
+            }
+        }
+    };
+}
+
+impl_approx_float!(f32, i32);
+impl_approx_float!(f64, i64);
+
+impl<T: Scalar + ApproxEq<T>> ApproxEq<T> for Matrix<T> {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance<T>) -> bool {
+        (0..3).all(|i| self.row(i).approx_eq(&other.row(i), tolerance))
+    }
+}
+
+impl<T: Scalar + ApproxEq<T>> ApproxEq<T> for Matrix4<T> {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance<T>) -> bool {
+        (0..4).all(|i| {
+            let (a, b) = (self.row(i), other.row(i));
+            (0..4).all(|j| a[j].approx_eq(&b[j], tolerance))
+        })
+    }
+}
+
+impl<T: Scalar + ApproxEq<T>> ApproxEq<T> for Quaternion<T> {
+    fn approx_eq(&self, other: &Self, tolerance: Tolerance<T>) -> bool {
+        self.w.approx_eq(&other.w, tolerance) && self.v.approx_eq(&other.v, tolerance)
+    }
+}
+
+macro_rules! impl_approx_wrapper {
+    ($kind: ident) => {
+        impl<T: Scalar + ApproxEq<T>> ApproxEq<T> for $kind<T> {
+            fn approx_eq(&self, other: &Self, tolerance: Tolerance<T>) -> bool {
+                self.0.approx_eq(&other.0, tolerance)
+            }
+        }
+    };
+}
+
+impl_approx_wrapper!(Point);
+impl_approx_wrapper!(Direction);
+impl_approx_wrapper!(Normal);
+
+/// Put the `Display` output of two values next to each other, line by
+/// line, so multi-line values like matrices can be compared at a glance.
+pub fn side_by_side(left: &str, right: &str) -> String {
+    let width = left.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+    let width = width.max("left".len());
+    let (left, right): (Vec<_>, Vec<_>) = (left.lines().collect(), right.lines().collect());
+
+    let mut out = format!("{:<width$}   right\n", "left");
+    for i in 0..left.len().max(right.len()) {
+        let l = left.get(i).copied().unwrap_or("");
+        let r = right.get(i).copied().unwrap_or("");
+        let mark = if l == r { ' ' } else { '|' };
+        // writing to a String cannot fail
+        let _ = writeln!(out, "{:<width$} {} {}", l, mark, r);
+    }
+    out
+}
+
+/// Assert that two values are equal within a `Tolerance`. On failure both
+/// values are printed side by side with their `Display` implementation,
+/// lines that differ are marked with `|`.
+#[macro_export]
+macro_rules! assert_approx_eq {
+    ($left: expr, $right: expr, $tolerance: expr $(,)?) => {
+        match (&$left, &$right, $tolerance) {
+            (left, right, tolerance) => {
+                if !$crate::math::ApproxEq::approx_eq(left, right, tolerance) {
+                    panic!(
+                        "assertion `left ≈ right` failed with {:?}\n{}",
+                        tolerance,
+                        $crate::math::side_by_side(&left.to_string(), &right.to_string())
+                    );
+                }
+            }
+        }
+    };
+}
+
+/// The opposite of `assert_approx_eq!`.
+#[macro_export]
+macro_rules! assert_approx_ne {
+    ($left: expr, $right: expr, $tolerance: expr $(,)?) => {
+        match (&$left, &$right, $tolerance) {
+            (left, right, tolerance) => {
+                if $crate::math::ApproxEq::approx_eq(left, right, tolerance) {
+                    panic!(
+                        "assertion `left ≉ right` failed with {:?}\n{}",
+                        tolerance,
+                        $crate::math::side_by_side(&left.to_string(), &right.to_string())
+                    );
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::{side_by_side, ApproxEq, Tolerance};
+    use crate::math::{Matrix, Vector};
+
+    #[test]
+    fn test_scalar() {
+        let a = 0.1f32 + 0.2;
+        assert!(a.approx_eq(&0.3, Tolerance::ulps(1)));
+        assert!(!1.0f32.approx_eq(&1.0001, Tolerance::ulps(4)));
+        assert!(1e6f64.approx_eq(&(1e6 + 1e-4), Tolerance::relative(1e-9)));
+        assert!(!1e6f64.approx_eq(&(1e6 + 1e-4), Tolerance::Absolute(1e-6)));
+
+        // relative and ULP comparisons fall back to the absolute one near zero
+        assert!(1e-20f64.approx_eq(&-1e-20, Tolerance::ulps(0)));
+        assert!(!1e-20f64.approx_eq(
+            &-1e-20,
+            Tolerance::Ulps {
+                epsilon: 0.0,
+                max_ulps: 4
+            }
+        ));
+
+        assert!(f32::INFINITY.approx_eq(&f32::INFINITY, Tolerance::Absolute(0.0)));
+        assert!(!f32::NAN.approx_eq(&f32::NAN, Tolerance::Absolute(1.0)));
+        assert!(!f32::MAX.approx_eq(&f32::INFINITY, Tolerance::ulps(1)));
+    }
+
+    #[test]
+    fn test_composite() {
+        let a = Vector::new(1.0, 2.0, 3.0);
+        assert_approx_eq!(
+            a,
+            a + Vector::new(1e-7, 0.0, 0.0),
+            Tolerance::relative(1e-6)
+        );
+        assert_approx_ne!(
+            a,
+            a + Vector::new(0.0, 0.0, 1e-3),
+            Tolerance::relative(1e-6)
+        );
+
+        let mat = Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 10.0);
+        let inv = mat.inverse().unwrap();
+        assert_approx_eq!(&mat * &inv, Matrix::identity(), Tolerance::Absolute(1e-6));
+    }
+
+    #[test]
+    fn test_failure_message() {
+        let left = Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0);
+        let right = Matrix::new(1.0, 2.0, 3.0, 4.0, 5.5, 6.0, 7.0, 8.0, 9.0);
+        let message = std::panic::catch_unwind(|| {
+            assert_approx_eq!(left, right, Tolerance::Absolute(0.1));
+        })
+        .unwrap_err();
+        let message = message.downcast_ref::<String>().unwrap();
+
+        let lines: Vec<_> = message.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].contains("Absolute(0.1)"));
+        assert!(lines[3].contains(" | "));
+
+        assert_eq!(
+            side_by_side("(1, 2)", "(1, 3)"),
+            "left     right\n(1, 2) | (1, 3)\n"
+        );
+    }
+}
@@ -0,0 +1,384 @@
+use super::{Matrix, Matrix4, Vector};
+
+#[cfg(feature = "simd")]
+use super::simd::soa as kernel;
+#[cfg(not(feature = "simd"))]
+use scalar as kernel;
+
+/// `Vector`s stored as one array per component, for processing many of
+/// them at once.
+///
+/// With the `simd` feature the kernels run on 8 lanes through the portable
+/// `wide` crate, otherwise they are plain loops. Run
+/// `cargo test --release --features simd bench -- --ignored --nocapture`
+/// to compare both paths.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VectorSoa {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub z: Vec<f32>,
+}
+
+impl VectorSoa {
+    pub fn from_vectors(vectors: &[Vector]) -> Self {
+        Self {
+            x: vectors.iter().map(|v| v.x).collect(),
+            y: vectors.iter().map(|v| v.y).collect(),
+            z: vectors.iter().map(|v| v.z).collect(),
+        }
+    }
+
+    pub fn to_vectors(&self) -> Vec<Vector> {
+        (0..self.len()).map(|i| self.get(i)).collect()
+    }
+
+    /// Write the vectors back into `vectors`, which should have the same
+    /// length.
+    pub fn write_to(&self, vectors: &mut [Vector]) {
+        assert_eq!(vectors.len(), self.len(), "Length mismatch.");
+        for (i, v) in vectors.iter_mut().enumerate() {
+            *v = self.get(i);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Vector {
+        Vector::new(self.x[index], self.y[index], self.z[index])
+    }
+
+    fn components(&self) -> [&[f32]; 3] {
+        [&self.x, &self.y, &self.z]
+    }
+
+    fn components_mut(&mut self) -> [&mut [f32]; 3] {
+        [&mut self.x, &mut self.y, &mut self.z]
+    }
+
+    pub fn dot_products(&self, other: &Self) -> Vec<f32> {
+        assert_eq!(self.len(), other.len(), "Length mismatch.");
+        let mut out = vec![0.0; self.len()];
+        kernel::dot(self.components(), other.components(), &mut out);
+        out
+    }
+
+    pub fn cross_products(&self, other: &Self) -> Self {
+        assert_eq!(self.len(), other.len(), "Length mismatch.");
+        let mut out = Self {
+            x: vec![0.0; self.len()],
+            y: vec![0.0; self.len()],
+            z: vec![0.0; self.len()],
+        };
+        kernel::cross(self.components(), other.components(), out.components_mut());
+        out
+    }
+
+    pub fn lengths(&self) -> Vec<f32> {
+        let mut out = vec![0.0; self.len()];
+        kernel::length(self.components(), &mut out);
+        out
+    }
+
+    pub fn normalize(&mut self) {
+        kernel::normalize(self.components_mut());
+    }
+
+    /// Multiply every vector by `mat`.
+    pub fn transform(&mut self, mat: &Matrix) {
+        kernel::linear(linear_rows(mat), self.components_mut());
+    }
+
+    /// See `Matrix4::transform_point`.
+    pub fn transform_points(&mut self, mat: &Matrix4) {
+        let rows = [mat.row(0), mat.row(1), mat.row(2), mat.row(3)];
+        kernel::points(rows, self.components_mut());
+    }
+
+    /// See `Matrix4::transform_direction`.
+    pub fn transform_directions(&mut self, mat: &Matrix4) {
+        kernel::linear(linear_rows(&mat.linear_part()), self.components_mut());
+    }
+}
+
+fn linear_rows(mat: &Matrix) -> [[f32; 3]; 3] {
+    [0, 1, 2].map(|i| {
+        let row = mat.row(i);
+        [row.x, row.y, row.z]
+    })
+}
+
+/// Transform a slice of points in place through a `VectorSoa`.
+pub fn transform_points(mat: &Matrix4, points: &mut [Vector]) {
+    let mut soa = VectorSoa::from_vectors(points);
+    soa.transform_points(mat);
+    soa.write_to(points);
+}
+
+/// Transform a slice of directions in place through a `VectorSoa`.
+pub fn transform_directions(mat: &Matrix4, directions: &mut [Vector]) {
+    let mut soa = VectorSoa::from_vectors(directions);
+    soa.transform_directions(mat);
+    soa.write_to(directions);
+}
+
+/// The plain loops, also used for the remainder of the SIMD kernels.
+pub(crate) mod scalar {
+    pub(crate) fn dot(a: [&[f32]; 3], b: [&[f32]; 3], out: &mut [f32]) {
+        for (i, each) in out.iter_mut().enumerate() {
+            *each = a[0][i] * b[0][i] + a[1][i] * b[1][i] + a[2][i] * b[2][i];
+        }
+    }
+
+    pub(crate) fn cross(a: [&[f32]; 3], b: [&[f32]; 3], out: [&mut [f32]; 3]) {
+        let [out_x, out_y, out_z] = out;
+        for i in 0..out_x.len() {
+            out_x[i] = a[1][i] * b[2][i] - a[2][i] * b[1][i];
+            out_y[i] = a[2][i] * b[0][i] - a[0][i] * b[2][i];
+            out_z[i] = a[0][i] * b[1][i] - a[1][i] * b[0][i];
+        }
+    }
+
+    pub(crate) fn length(a: [&[f32]; 3], out: &mut [f32]) {
+        for (i, each) in out.iter_mut().enumerate() {
+            *each = (a[0][i] * a[0][i] + a[1][i] * a[1][i] + a[2][i] * a[2][i]).sqrt();
+        }
+    }
+
+    pub(crate) fn normalize(v: [&mut [f32]; 3]) {
+        let [x, y, z] = v;
+        for i in 0..x.len() {
+            let length = (x[i] * x[i] + y[i] * y[i] + z[i] * z[i]).sqrt();
+            x[i] /= length;
+            y[i] /= length;
+            z[i] /= length;
+        }
+    }
+
+    pub(crate) fn linear(m: [[f32; 3]; 3], v: [&mut [f32]; 3]) {
+        let [x, y, z] = v;
+        for i in 0..x.len() {
+            let (vx, vy, vz) = (x[i], y[i], z[i]);
+            x[i] = m[0][0] * vx + m[0][1] * vy + m[0][2] * vz;
+            y[i] = m[1][0] * vx + m[1][1] * vy + m[1][2] * vz;
+            z[i] = m[2][0] * vx + m[2][1] * vy + m[2][2] * vz;
+        }
+    }
+
+    pub(crate) fn points(m: [[f32; 4]; 4], v: [&mut [f32]; 3]) {
+        let [x, y, z] = v;
+        for i in 0..x.len() {
+            let (vx, vy, vz) = (x[i], y[i], z[i]);
+            let w = m[3][0] * vx + m[3][1] * vy + m[3][2] * vz + m[3][3];
+            x[i] = (m[0][0] * vx + m[0][1] * vy + m[0][2] * vz + m[0][3]) / w;
+            y[i] = (m[1][0] * vx + m[1][1] * vy + m[1][2] * vz + m[1][3]) / w;
+            z[i] = (m[2][0] * vx + m[2][1] * vy + m[2][2] * vz + m[2][3]) / w;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{hint::black_box, time::Instant};
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::{kernel, scalar, transform_points, VectorSoa};
+    use crate::math::{Matrix, Matrix4, Vector};
+
+    fn random_vectors(count: usize) -> Vec<Vector> {
+        let mut rng = StdRng::seed_from_u64(11);
+        (0..count)
+            .map(|_| {
+                Vector::new(
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                    rng.gen_range(-10.0..10.0),
+                )
+            })
+            .collect()
+    }
+
+    fn assert_close(a: Vector, b: Vector) {
+        assert!(
+            (a - b).length() <= 1e-5 * (1.0 + b.length()),
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn test_soa_matches_vector() {
+        // not a multiple of the lane count, to cover the remainder
+        let a = random_vectors(37);
+        let b = random_vectors(38)[1..].to_vec();
+        let (soa_a, soa_b) = (VectorSoa::from_vectors(&a), VectorSoa::from_vectors(&b));
+        assert_eq!(soa_a.to_vectors(), a);
+
+        let dots = soa_a.dot_products(&soa_b);
+        let crosses = soa_a.cross_products(&soa_b);
+        let lengths = soa_a.lengths();
+        let mut normalized = soa_a.clone();
+        normalized.normalize();
+        for i in 0..a.len() {
+            assert!((dots[i] - a[i].dot_product(&b[i])).abs() < 1e-4);
+            assert_close(crosses.get(i), a[i].cross_product(&b[i]));
+            assert!((lengths[i] - a[i].length()).abs() < 1e-5);
+            assert_close(normalized.get(i), a[i] / a[i].length());
+        }
+
+        let mat = Matrix::new(1.0, 2.0, 3.0, -4.0, 5.0, 6.0, 7.0, 8.0, -9.0);
+        let mut linear = soa_a.clone();
+        linear.transform(&mat);
+
+        let mat4 = Matrix4::perspective(1.0, 1.5, 0.1, 100.0)
+            * Matrix4::translation(&Vector::new(0.0, 0.0, -30.0));
+        let mut points = a.clone();
+        transform_points(&mat4, &mut points);
+        let mut directions = soa_a.clone();
+        directions.transform_directions(&mat4);
+
+        for i in 0..a.len() {
+            assert_close(linear.get(i), &mat * a[i]);
+            assert_close(points[i], mat4.transform_point(&a[i]));
+            assert_close(directions.get(i), mat4.transform_direction(&a[i]));
+        }
+    }
+
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_soa_kernels() {
+        let count = 1 << 22;
+        let a = VectorSoa::from_vectors(&random_vectors(count));
+        let b = VectorSoa::from_vectors(&random_vectors(count + 1)[1..]);
+        let mat4 = Matrix4::rotation(&Vector::new(1.0, 1.0, 0.0), 0.3)
+            * Matrix4::translation(&Vector::new(1.0, 2.0, 3.0));
+        let rows = [mat4.row(0), mat4.row(1), mat4.row(2), mat4.row(3)];
+        let mut out = vec![0.0; count];
+
+        let time = |name: &str, f: &mut dyn FnMut()| {
+            let start = Instant::now();
+            for _ in 0..10 {
+                f();
+            }
+            println!("{:<24}{:?}", name, start.elapsed() / 10);
+        };
+
+        time("dot, scalar", &mut || {
+            scalar::dot([&a.x, &a.y, &a.z], [&b.x, &b.y, &b.z], &mut out)
+        });
+        time("dot, kernel", &mut || {
+            kernel::dot([&a.x, &a.y, &a.z], [&b.x, &b.y, &b.z], &mut out)
+        });
+
+        let mut c = a.clone();
+        time("normalize, scalar", &mut || {
+            scalar::normalize([&mut c.x, &mut c.y, &mut c.z])
+        });
+        time("normalize, kernel", &mut || {
+            kernel::normalize([&mut c.x, &mut c.y, &mut c.z])
+        });
+
+        time("points, scalar", &mut || {
+            scalar::points(rows, [&mut c.x, &mut c.y, &mut c.z])
+        });
+        time("points, kernel", &mut || {
+            kernel::points(rows, [&mut c.x, &mut c.y, &mut c.z])
+        });
+
+        let vectors = a.to_vectors();
+        time("points, per Vector", &mut || {
+            for v in &vectors {
+                black_box(mat4.transform_point(v));
+            }
+        });
+
+        // the per-vector f32x4 versions against the scalar operators
+        #[cfg(feature = "simd")]
+        {
+            use crate::math::simd;
+
+            let others = b.to_vectors();
+            let pairs = || vectors.iter().zip(&others);
+            time("dot, Vector", &mut || {
+                for (u, v) in pairs() {
+                    black_box(u.dot_product(v));
+                }
+            });
+            time("dot, f32x4", &mut || {
+                for (u, v) in pairs() {
+                    black_box(simd::dot_product(u, v));
+                }
+            });
+            time("cross, Vector", &mut || {
+                for (u, v) in pairs() {
+                    black_box(u.cross_product(v));
+                }
+            });
+            time("cross, f32x4", &mut || {
+                for (u, v) in pairs() {
+                    black_box(simd::cross_product(u, v));
+                }
+            });
+            time("length, Vector", &mut || {
+                for v in &vectors {
+                    black_box(v.length());
+                }
+            });
+            time("length, f32x4", &mut || {
+                for v in &vectors {
+                    black_box(simd::length(v));
+                }
+            });
+            time("normalize, Vector", &mut || {
+                for v in &vectors {
+                    black_box(v.normalize());
+                }
+            });
+            time("normalize, f32x4", &mut || {
+                for v in &vectors {
+                    black_box(simd::normalize(v));
+                }
+            });
+
+            let mat = Matrix::new(1.0, 2.0, 3.0, -4.0, 5.0, 6.0, 7.0, 8.0, -9.0);
+            let matrices: Vec<Matrix> = vectors
+                .chunks_exact(3)
+                .map(|rows| Matrix::from_vectors(rows[0], rows[1], rows[2]))
+                .collect();
+            time("3x3 product, Matrix", &mut || {
+                for m in &matrices {
+                    black_box(&mat * m);
+                }
+            });
+            time("3x3 product, f32x4", &mut || {
+                for m in &matrices {
+                    black_box(simd::mul_matrix(&mat, m));
+                }
+            });
+
+            let transforms: Vec<Matrix4> = vectors
+                .iter()
+                .take(count / 16)
+                .map(Matrix4::translation)
+                .collect();
+            time("4x4 product, Matrix4", &mut || {
+                for m in &transforms {
+                    black_box(&mat4 * m);
+                }
+            });
+            time("4x4 product, f32x4", &mut || {
+                for m in &transforms {
+                    black_box(simd::mul_matrix4(&mat4, m));
+                }
+            });
+        }
+    }
+}
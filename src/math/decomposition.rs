@@ -0,0 +1,568 @@
+// the algorithms read better with explicit indices
+#![allow(clippy::needless_range_loop)]
+
+use std::cmp::Ordering;
+
+use super::{Matrix, Real, Vector};
+
+/// More sweeps than a 3x3 Jacobi iteration should ever need:
+/// `symmetric_eigen` and `svd` iterate until the off-diagonal part is below
+/// rounding error, which takes a handful of sweeps.
+const MAX_SWEEPS: usize = 64;
+
+const PAIRS: [(usize, usize); 3] = [(0, 1), (0, 2), (1, 2)];
+
+/// `P A = L U`, see `Matrix::lu`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lu<T = f32> {
+    /// Unit lower triangular.
+    pub lower: Matrix<T>,
+    pub upper: Matrix<T>,
+    /// Row `i` of `P A` is row `permutation[i]` of `A`.
+    pub permutation: [usize; 3],
+}
+
+/// `A = Q R`, see `Matrix::qr`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Qr<T = f32> {
+    /// Orthogonal.
+    pub q: Matrix<T>,
+    /// Upper triangular with a non-negative diagonal.
+    pub r: Matrix<T>,
+}
+
+/// `A = V diag(values) V^T`, see `Matrix::symmetric_eigen`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymmetricEigen<T = f32> {
+    /// Eigenvalues in descending order.
+    pub values: Vector<T>,
+    /// The matching unit eigenvectors as columns, forming a rotation.
+    pub vectors: Matrix<T>,
+}
+
+/// `A = U diag(singular_values) V^T`, see `Matrix::svd`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Svd<T = f32> {
+    pub u: Matrix<T>,
+    /// Non-negative, in descending order.
+    pub singular_values: Vector<T>,
+    pub v: Matrix<T>,
+}
+
+/// `A = R S`, see `Matrix::polar`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polar<T = f32> {
+    /// Orthogonal. A rotation if the determinant of `A` is positive.
+    pub rotation: Matrix<T>,
+    /// Symmetric positive semi-definite.
+    pub stretch: Matrix<T>,
+}
+
+impl<T: Real> Matrix<T> {
+    /// LU decomposition with partial pivoting. Always succeeds, but if the
+    /// matrix is singular, `upper` has a zero on its diagonal.
+    pub fn lu(&self) -> Lu<T> {
+        let mut a = self.to_rows();
+        let mut permutation = [0, 1, 2];
+
+        for k in 0..3 {
+            // the largest pivot keeps the multipliers at most 1 in magnitude
+            let pivot = (k + 1..3).fold(k, |best, i| {
+                if a[i][k].abs() > a[best][k].abs() {
+                    i
+                } else {
+                    best
+                }
+            });
+            a.swap(k, pivot);
+            permutation.swap(k, pivot);
+
+            if a[k][k] == T::ZERO {
+                continue;
+            }
+            for i in k + 1..3 {
+                let factor = a[i][k] / a[k][k];
+                a[i][k] = factor;
+                for j in k + 1..3 {
+                    a[i][j] -= factor * a[k][j];
+                }
+            }
+        }
+
+        let mut lower = [[T::ZERO; 3]; 3];
+        let mut upper = [[T::ZERO; 3]; 3];
+        for i in 0..3 {
+            for j in 0..3 {
+                match i.cmp(&j) {
+                    Ordering::Greater => lower[i][j] = a[i][j],
+                    Ordering::Equal => {
+                        lower[i][j] = T::ONE;
+                        upper[i][j] = a[i][j];
+                    }
+                    Ordering::Less => upper[i][j] = a[i][j],
+                }
+            }
+        }
+
+        Lu {
+            lower: Matrix::from_rows(lower),
+            upper: Matrix::from_rows(upper),
+            permutation,
+        }
+    }
+
+    /// QR decomposition with Householder reflections.
+    pub fn qr(&self) -> Qr<T> {
+        let mut r = self.to_rows();
+        let mut q = Matrix::<T>::identity().to_rows();
+        let two = T::from_f64(2.0);
+
+        for k in 0..2 {
+            let norm = (k..3)
+                .map(|i| r[i][k] * r[i][k])
+                .fold(T::ZERO, |a, b| a + b);
+            let norm = norm.sqrt();
+            if norm == T::ZERO {
+                continue;
+            }
+
+            // reflect column k onto the axis, away from the current value to
+            // avoid cancellation
+            let mut v = [T::ZERO; 3];
+            for i in k..3 {
+                v[i] = r[i][k];
+            }
+            v[k] += norm.copysign(r[k][k]);
+            let v_squared = v.iter().fold(T::ZERO, |a, &b| a + b * b);
+
+            // R = H R and Q = Q H with H = I - 2 v v^T / (v^T v)
+            for j in 0..3 {
+                let s = (k..3).fold(T::ZERO, |a, i| a + v[i] * r[i][j]) * two / v_squared;
+                for i in k..3 {
+                    r[i][j] -= s * v[i];
+                }
+            }
+            for row in q.iter_mut() {
+                let s = (k..3).fold(T::ZERO, |a, j| a + row[j] * v[j]) * two / v_squared;
+                for j in k..3 {
+                    row[j] -= s * v[j];
+                }
+            }
+        }
+
+        for k in 0..3 {
+            for i in k + 1..3 {
+                r[i][k] = T::ZERO;
+            }
+            // make the factorization unique
+            if r[k][k] < T::ZERO {
+                for j in k..3 {
+                    r[k][j] = -r[k][j];
+                }
+                for row in q.iter_mut() {
+                    row[k] = -row[k];
+                }
+            }
+        }
+
+        Qr {
+            q: Matrix::from_rows(q),
+            r: Matrix::from_rows(r),
+        }
+    }
+
+    /// Eigen decomposition of a symmetric matrix with cyclic Jacobi
+    /// rotations. Only the upper triangle is read.
+    ///
+    /// For a covariance matrix the eigenvectors are the principal axes, and
+    /// the last one is the normal of the best fitting plane.
+    pub fn symmetric_eigen(&self) -> SymmetricEigen<T> {
+        let mut a = self.to_rows();
+        for (p, q) in PAIRS {
+            a[q][p] = a[p][q];
+        }
+        let mut v = Matrix::<T>::identity().to_rows();
+
+        let scale = a.iter().flatten().fold(T::ZERO, |acc, &x| acc + x * x);
+        for _ in 0..MAX_SWEEPS {
+            let off = PAIRS
+                .iter()
+                .fold(T::ZERO, |acc, &(p, q)| acc + a[p][q] * a[p][q]);
+            if off <= scale * T::EPSILON * T::EPSILON {
+                break;
+            }
+
+            for (p, q) in PAIRS {
+                if a[p][q] == T::ZERO {
+                    continue;
+                }
+                let (c, s) = jacobi_rotation(a[p][p], a[q][q], a[p][q]);
+                // A = J^T A J, V = V J
+                for row in a.iter_mut() {
+                    (row[p], row[q]) = (c * row[p] - s * row[q], s * row[p] + c * row[q]);
+                }
+                for k in 0..3 {
+                    (a[p][k], a[q][k]) = (c * a[p][k] - s * a[q][k], s * a[p][k] + c * a[q][k]);
+                }
+                for row in v.iter_mut() {
+                    (row[p], row[q]) = (c * row[p] - s * row[q], s * row[p] + c * row[q]);
+                }
+            }
+        }
+
+        let mut order = [0, 1, 2];
+        order.sort_by(|&i, &j| compare(a[j][j], a[i][i]));
+        let values = Vector::from(order.map(|i| a[i][i]));
+        let vectors = order.map(|i| Vector::new(v[0][i], v[1][i], v[2][i]));
+
+        SymmetricEigen {
+            values,
+            vectors: right_handed(vectors),
+        }
+    }
+
+    /// Singular value decomposition with one-sided Jacobi rotations, which
+    /// orthogonalize the columns without forming `A^T A`, so small singular
+    /// values stay accurate.
+    ///
+    /// `u` and `v` are orthogonal but not necessarily rotations. If the
+    /// matrix is rank deficient, the columns of `u` belonging to zero
+    /// singular values are completed to an orthonormal basis.
+    pub fn svd(&self) -> Svd<T> {
+        let mut a = self.to_rows();
+        let mut v = Matrix::<T>::identity().to_rows();
+
+        for _ in 0..MAX_SWEEPS {
+            let mut rotated = false;
+            for (p, q) in PAIRS {
+                let (mut alpha, mut beta, mut gamma) = (T::ZERO, T::ZERO, T::ZERO);
+                for row in a.iter() {
+                    alpha += row[p] * row[p];
+                    beta += row[q] * row[q];
+                    gamma += row[p] * row[q];
+                }
+                if gamma.abs() <= T::EPSILON * (alpha * beta).sqrt() {
+                    continue;
+                }
+                rotated = true;
+
+                let (c, s) = jacobi_rotation(alpha, beta, gamma);
+                for row in a.iter_mut().chain(v.iter_mut()) {
+                    (row[p], row[q]) = (c * row[p] - s * row[q], s * row[p] + c * row[q]);
+                }
+            }
+            if !rotated {
+                break;
+            }
+        }
+
+        let columns = [0, 1, 2].map(|i| Vector::new(a[0][i], a[1][i], a[2][i]));
+        let mut order = [0, 1, 2];
+        order.sort_by(|&i, &j| compare(columns[j].length(), columns[i].length()));
+        let singular_values = Vector::from(order.map(|i| columns[i].length()));
+        let v_columns = order.map(|i| Vector::new(v[0][i], v[1][i], v[2][i]));
+
+        // columns of singular values below rounding error are noise
+        let threshold = singular_values.x * T::EPSILON * T::from_f64(8.0);
+        let mut u = order.map(|i| columns[i]);
+        if singular_values.x <= threshold {
+            u = Matrix::<T>::identity().to_rows().map(Vector::from);
+        } else if singular_values.y <= threshold {
+            u[0] = u[0].normalize();
+            (u[1], u[2]) = u[0].orthonormal_basis_revised().unwrap();
+        } else {
+            u[0] = u[0].normalize();
+            u[1] = u[1].normalize();
+            u[2] = if singular_values.z <= threshold {
+                u[0].cross_product(&u[1])
+            } else {
+                u[2].normalize()
+            };
+        }
+
+        Svd {
+            u: Matrix::from_columns(u[0], u[1], u[2]),
+            singular_values,
+            v: Matrix::from_columns(v_columns[0], v_columns[1], v_columns[2]),
+        }
+    }
+
+    /// Polar decomposition, which splits a deformation into a rotation
+    /// and a stretch along orthogonal axes. Computed from the `svd` as
+    /// `R = U V^T` and `S = V Σ V^T`.
+    pub fn polar(&self) -> Polar<T> {
+        let Svd {
+            u,
+            singular_values,
+            v,
+        } = self.svd();
+        let v_t = v.transpose();
+        let sigma = Matrix::from_diagonal(singular_values.x, singular_values.y, singular_values.z);
+
+        Polar {
+            rotation: &u * &v_t,
+            stretch: &(&v * &sigma) * &v_t,
+        }
+    }
+}
+
+impl<T: Real> Lu<T> {
+    /// The permutation as a matrix `P`.
+    pub fn permutation_matrix(&self) -> Matrix<T> {
+        let identity = Matrix::<T>::identity();
+        Matrix::from_vectors(
+            identity.row(self.permutation[0]),
+            identity.row(self.permutation[1]),
+            identity.row(self.permutation[2]),
+        )
+    }
+
+    pub fn determinant(&self) -> T {
+        let p = self.permutation;
+        let inversions = PAIRS.iter().filter(|&&(i, j)| p[i] > p[j]).count();
+        let sign = if inversions % 2 == 0 { T::ONE } else { -T::ONE };
+
+        let u = self.upper.to_rows();
+        sign * u[0][0] * u[1][1] * u[2][2]
+    }
+
+    /// Solve `A x = b` by forward and back substitution. Return `None` if
+    /// the matrix is singular up to rounding, i.e. a pivot is at most
+    /// `3 * EPSILON` times the largest element of `U`, as in
+    /// `DynamicMatrix::solve_matrix`.
+    pub fn solve(&self, b: &Vector<T>) -> Option<Vector<T>> {
+        let (lower, upper) = (self.lower.to_rows(), self.upper.to_rows());
+        let largest = upper
+            .iter()
+            .flatten()
+            .fold(T::ZERO, |acc, x| acc.max(x.abs()));
+        let tolerance = T::from_f64(3.0) * T::EPSILON * largest;
+
+        let mut y = [T::ZERO; 3];
+        for i in 0..3 {
+            y[i] = b[self.permutation[i]];
+            for j in 0..i {
+                y[i] -= lower[i][j] * y[j];
+            }
+        }
+
+        let mut x = [T::ZERO; 3];
+        for i in (0..3).rev() {
+            if upper[i][i].abs() <= tolerance {
+                return None;
+            }
+            x[i] = y[i];
+            for j in i + 1..3 {
+                x[i] -= upper[i][j] * x[j];
+            }
+            x[i] /= upper[i][i];
+        }
+
+        Some(x.into())
+    }
+}
+
+/// The rotation `(cos, sin)` that zeroes the off-diagonal element of the
+/// symmetric 2x2 matrix `[[app, apq], [apq, aqq]]`, choosing the smaller
+/// of the two possible angles.
+fn jacobi_rotation<T: Real>(app: T, aqq: T, apq: T) -> (T, T) {
+    let theta = (aqq - app) / (T::from_f64(2.0) * apq);
+    // tan of the angle, written to avoid cancellation
+    let t = T::ONE.copysign(theta) / (theta.abs() + (theta * theta + T::ONE).sqrt());
+    let c = T::ONE / (t * t + T::ONE).sqrt();
+    (c, t * c)
+}
+
+/// A total order for sorting, even if NaN shows up.
+fn compare<T: Real>(a: T, b: T) -> Ordering {
+    a.to_f64().total_cmp(&b.to_f64())
+}
+
+/// Flip the last column if needed, so the columns form a rotation.
+fn right_handed<T: Real>(columns: [Vector<T>; 3]) -> Matrix<T> {
+    let [a, b, c] = columns;
+    if a.cross_product(&b).dot_product(&c) < T::ZERO {
+        Matrix::from_columns(a, b, -c)
+    } else {
+        Matrix::from_columns(a, b, c)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::assert_approx_eq;
+    use crate::math::{Matrix, Matrix4, Tolerance, Vector};
+
+    const TOLERANCE: Tolerance<f64> = Tolerance::Absolute(1e-12);
+
+    fn random_matrices() -> Vec<Matrix<f64>> {
+        let mut rng = StdRng::seed_from_u64(15);
+        (0..200)
+            .map(|_| {
+                Matrix::from_rows([[(); 3]; 3].map(|row| row.map(|_| rng.gen_range(-2.0..2.0))))
+            })
+            .collect()
+    }
+
+    /// Matrices of rank 2, 1 and 0, and a nearly singular one.
+    fn degenerate_matrices() -> Vec<Matrix<f64>> {
+        let rotation = Matrix4::rotation(&Vector::new(1.0, 2.0, 3.0), 0.7).linear_part();
+        let rotated =
+            |a, b, c| &(&rotation * &Matrix::from_diagonal(a, b, c)) * &rotation.transpose();
+        vec![
+            Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0),
+            Matrix::new(1.0, 0.0, 2.0, 3.0, 0.0, 4.0, 5.0, 0.0, 6.0),
+            rotated(2.0, 1.0, 0.0),
+            rotated(0.0, 3.0, 0.0),
+            rotated(1.0, 1.0, 1e-9),
+            Matrix::zero(),
+        ]
+    }
+
+    fn assert_orthogonal(m: &Matrix<f64>) {
+        assert_approx_eq!(&m.transpose() * m, Matrix::identity(), TOLERANCE);
+    }
+
+    #[test]
+    fn test_lu() {
+        for a in random_matrices().into_iter().chain(degenerate_matrices()) {
+            let lu = a.lu();
+            assert_approx_eq!(
+                &lu.permutation_matrix() * &a,
+                &lu.lower * &lu.upper,
+                TOLERANCE
+            );
+            assert_approx_eq!(lu.determinant(), a.determinant(), TOLERANCE);
+            // pivoting keeps the multipliers small
+            assert!(lu.lower.to_rows().iter().flatten().all(|x| x.abs() <= 1.0));
+
+            let b = Vector::new(1.0, -2.0, 0.5);
+            if a.determinant().abs() > 1e-3 {
+                let x = lu.solve(&b).unwrap();
+                assert_approx_eq!(&a * x, b, Tolerance::Absolute(1e-10));
+            }
+        }
+
+        let singular = Matrix::new(1.0, 0.0, 2.0, 3.0, 0.0, 4.0, 5.0, 0.0, 6.0);
+        assert!(singular.lu().solve(&Vector::new(1.0, 1.0, 1.0)).is_none());
+        // rank 2, but rounding leaves a tiny last pivot
+        let singular = Matrix::new(0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9);
+        assert_ne!(singular.lu().determinant(), 0.0);
+        assert!(singular.lu().solve(&Vector::new(1.0, 1.0, 1.0)).is_none());
+    }
+
+    #[test]
+    fn test_qr() {
+        for a in random_matrices().into_iter().chain(degenerate_matrices()) {
+            let qr = a.qr();
+            assert_orthogonal(&qr.q);
+            assert_approx_eq!(&qr.q * &qr.r, a, TOLERANCE);
+            let r = qr.r.to_rows();
+            assert_eq!([r[1][0], r[2][0], r[2][1]], [0.0; 3]);
+            assert!(r[0][0] >= 0.0 && r[1][1] >= 0.0 && r[2][2] >= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_symmetric_eigen() {
+        for m in random_matrices().into_iter().chain(degenerate_matrices()) {
+            let a = &m + &m.transpose();
+            let eigen = a.symmetric_eigen();
+            let values = eigen.values;
+            assert!(values.x >= values.y && values.y >= values.z);
+            assert_orthogonal(&eigen.vectors);
+            assert_approx_eq!(eigen.vectors.determinant(), 1.0, TOLERANCE);
+
+            let diagonal = Matrix::from_diagonal(values.x, values.y, values.z);
+            let reconstructed = &(&eigen.vectors * &diagonal) * &eigen.vectors.transpose();
+            assert_approx_eq!(reconstructed, a, Tolerance::Absolute(1e-11));
+        }
+
+        // repeated eigenvalues
+        let eigen = Matrix::from_diagonal(2.0, 2.0, 2.0).symmetric_eigen();
+        assert_eq!(eigen.values, Vector::new(2.0, 2.0, 2.0));
+        assert_eq!(eigen.vectors, Matrix::identity());
+    }
+
+    #[test]
+    fn test_plane_fit() {
+        // noisy points around the plane through `center` with `normal`
+        let mut rng = StdRng::seed_from_u64(3);
+        let (center, normal) = (
+            Vector::new(1.0, 2.0, 3.0),
+            Vector::new(1.0, -1.0, 2.0).normalize(),
+        );
+        let (u, v) = normal.orthonormal_basis_revised().unwrap();
+        let points: Vec<Vector<f64>> = (0..1000)
+            .map(|_| {
+                center
+                    + u * rng.gen_range(-5.0..5.0)
+                    + v * rng.gen_range(-2.0..2.0)
+                    + normal * rng.gen_range(-1e-3..1e-3)
+            })
+            .collect();
+
+        let mean = points
+            .iter()
+            .fold(Vector::new(0.0, 0.0, 0.0), |a, &b| a + b)
+            / points.len() as f64;
+        let covariance = points.iter().fold(Matrix::zero(), |acc, p| {
+            let d = p - mean;
+            &acc + &Matrix::from_columns(d * d.x, d * d.y, d * d.z)
+        });
+
+        let eigen = covariance.symmetric_eigen();
+        let fitted = eigen.vectors.column(2);
+        assert!(1.0 - fitted.dot_product(&normal).abs() < 1e-6);
+        // the longest axis of the points
+        assert!(1.0 - eigen.vectors.column(0).dot_product(&u).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_svd() {
+        for a in random_matrices().into_iter().chain(degenerate_matrices()) {
+            let svd = a.svd();
+            let sigma = svd.singular_values;
+            assert!(sigma.x >= sigma.y && sigma.y >= sigma.z && sigma.z >= 0.0);
+            assert_orthogonal(&svd.u);
+            assert_orthogonal(&svd.v);
+
+            let diagonal = Matrix::from_diagonal(sigma.x, sigma.y, sigma.z);
+            let reconstructed = &(&svd.u * &diagonal) * &svd.v.transpose();
+            assert_approx_eq!(reconstructed, a, TOLERANCE);
+        }
+
+        // small singular values keep their relative accuracy
+        let rotation = Matrix4::rotation(&Vector::new(-1.0, 0.5, 2.0), 1.1).linear_part();
+        let a = &rotation * &Matrix::from_diagonal(1.0, 1e-5, 1e-10);
+        let sigma = a.svd().singular_values;
+        assert_approx_eq!(
+            sigma,
+            Vector::new(1.0, 1e-5, 1e-10),
+            Tolerance::relative(1e-9)
+        );
+    }
+
+    #[test]
+    fn test_polar() {
+        for a in random_matrices().into_iter().chain(degenerate_matrices()) {
+            let polar = a.polar();
+            assert_orthogonal(&polar.rotation);
+            assert_approx_eq!(polar.stretch, polar.stretch.transpose(), TOLERANCE);
+            assert!(polar.stretch.symmetric_eigen().values.z > -1e-12);
+            assert_approx_eq!(&polar.rotation * &polar.stretch, a, TOLERANCE);
+            if a.determinant() > 1e-6 {
+                assert_approx_eq!(polar.rotation.determinant(), 1.0, TOLERANCE);
+            }
+        }
+
+        // rotate after a stretch along the axes
+        let rotation = Matrix4::rotation(&Vector::new(0.0, 1.0, 1.0), -0.4).linear_part();
+        let stretch = Matrix::from_diagonal(2.0, 0.5, 1.5);
+        let polar = (&rotation * &stretch).polar();
+        assert_approx_eq!(polar.rotation, rotation, TOLERANCE);
+        assert_approx_eq!(polar.stretch, stretch, TOLERANCE);
+    }
+}
@@ -0,0 +1,484 @@
+use std::{
+    fmt::{Display, Formatter, LowerExp},
+    ops::{Add, Index, IndexMut, Mul, Sub},
+};
+
+use super::{
+    format::{write_matrix, Notation},
+    Matrix, Real, Scalar,
+};
+
+/// A dense matrix of any size, stored row by row on the heap, e.g. for
+/// calibration and least squares fitting where the number of equations is
+/// only known at runtime.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RawDynamicMatrix<T>")
+)]
+pub struct DynamicMatrix<T = f32> {
+    rows: usize,
+    columns: usize,
+    data: Vec<T>,
+}
+
+/// What is deserialized before the length is checked as in `new`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawDynamicMatrix<T> {
+    rows: usize,
+    columns: usize,
+    data: Vec<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<RawDynamicMatrix<T>> for DynamicMatrix<T> {
+    type Error = String;
+
+    fn try_from(raw: RawDynamicMatrix<T>) -> Result<Self, String> {
+        if raw.rows.checked_mul(raw.columns) != Some(raw.data.len()) {
+            return Err(format!(
+                "Length mismatch: {} elements for {}x{}.",
+                raw.data.len(),
+                raw.rows,
+                raw.columns
+            ));
+        }
+        Ok(Self {
+            rows: raw.rows,
+            columns: raw.columns,
+            data: raw.data,
+        })
+    }
+}
+
+impl<T: Scalar> DynamicMatrix<T> {
+    /// Build a matrix from its elements in row-major order.
+    pub fn new(rows: usize, columns: usize, data: Vec<T>) -> Self {
+        assert_eq!(data.len(), rows * columns, "Length mismatch.");
+        Self {
+            rows,
+            columns,
+            data,
+        }
+    }
+
+    pub fn from_fn(rows: usize, columns: usize, mut f: impl FnMut(usize, usize) -> T) -> Self {
+        let data = (0..rows * columns)
+            .map(|i| f(i / columns, i % columns))
+            .collect();
+        Self::new(rows, columns, data)
+    }
+
+    /// Build a matrix from rows, which should all have the same length.
+    pub fn from_rows<R: AsRef<[T]>>(rows: &[R]) -> Self {
+        let columns = rows.first().map_or(0, |row| row.as_ref().len());
+        let mut data = Vec::with_capacity(rows.len() * columns);
+        for row in rows {
+            assert_eq!(row.as_ref().len(), columns, "Length mismatch.");
+            data.extend_from_slice(row.as_ref());
+        }
+        Self::new(rows.len(), columns, data)
+    }
+
+    /// A matrix with a single column.
+    pub fn from_column(column: &[T]) -> Self {
+        Self::new(column.len(), 1, column.to_vec())
+    }
+
+    pub fn zero(rows: usize, columns: usize) -> Self {
+        Self::new(rows, columns, vec![T::ZERO; rows * columns])
+    }
+
+    pub fn identity(size: usize) -> Self {
+        Self::from_fn(size, size, |i, j| if i == j { T::ONE } else { T::ZERO })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.rows == self.columns
+    }
+
+    /// The elements in row-major order.
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn row(&self, index: usize) -> &[T] {
+        &self.data[index * self.columns..(index + 1) * self.columns]
+    }
+
+    pub fn column(&self, index: usize) -> Vec<T> {
+        (0..self.rows).map(|i| self[(i, index)]).collect()
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self::from_fn(self.columns, self.rows, |i, j| self[(j, i)])
+    }
+
+    /// Multiply by a column vector given as a slice.
+    pub fn mul_vector(&self, vector: &[T]) -> Vec<T> {
+        assert_eq!(vector.len(), self.columns, "Dimension mismatch.");
+        (0..self.rows)
+            .map(|i| {
+                self.row(i)
+                    .iter()
+                    .zip(vector)
+                    .fold(T::ZERO, |acc, (&a, &b)| acc + a * b)
+            })
+            .collect()
+    }
+}
+
+impl<T: Real> DynamicMatrix<T> {
+    /// Solve `A X = B` by Gaussian elimination with partial pivoting, where
+    /// `A` is square. Return `None` if `A` is singular up to rounding, i.e. a
+    /// pivot is at most `n * EPSILON` times the largest element of `A`.
+    pub fn solve_matrix(&self, b: &Self) -> Option<Self> {
+        assert!(self.is_square(), "The matrix should be square.");
+        assert_eq!(b.rows, self.rows, "Dimension mismatch.");
+        let (n, m) = (self.rows, b.columns);
+        let tolerance = self.tolerance(n);
+        let mut a = self.clone();
+        let mut x = b.clone();
+
+        for k in 0..n {
+            let pivot = (k + 1..n).fold(k, |best, i| {
+                if a[(i, k)].abs() > a[(best, k)].abs() {
+                    i
+                } else {
+                    best
+                }
+            });
+            if a[(pivot, k)].abs() <= tolerance {
+                return None;
+            }
+            a.swap_rows(k, pivot);
+            x.swap_rows(k, pivot);
+
+            for i in k + 1..n {
+                let factor = a[(i, k)] / a[(k, k)];
+                if factor == T::ZERO {
+                    continue;
+                }
+                for j in k..n {
+                    let value = a[(k, j)];
+                    a[(i, j)] -= factor * value;
+                }
+                for j in 0..m {
+                    let value = x[(k, j)];
+                    x[(i, j)] -= factor * value;
+                }
+            }
+        }
+
+        // back substitution
+        for k in (0..n).rev() {
+            for j in 0..m {
+                let mut value = x[(k, j)];
+                for i in k + 1..n {
+                    value -= a[(k, i)] * x[(i, j)];
+                }
+                x[(k, j)] = value / a[(k, k)];
+            }
+        }
+        Some(x)
+    }
+
+    /// Solve `A x = b`, see `solve_matrix`.
+    pub fn solve(&self, b: &[T]) -> Option<Vec<T>> {
+        self.solve_matrix(&Self::from_column(b)).map(|x| x.data)
+    }
+
+    /// Return `None` if the matrix is singular, see `solve_matrix`.
+    pub fn inverse(&self) -> Option<Self> {
+        self.solve_matrix(&Self::identity(self.rows))
+    }
+
+    /// Find `x` minimizing `|A x - b|` for an overdetermined system, i.e.
+    /// at least as many rows as columns. Householder QR is used instead of
+    /// the normal equations `A^T A x = A^T b`, which would square the
+    /// condition number. Return `None` if the columns are linearly
+    /// dependent up to rounding, i.e. a diagonal element of `R` is at most
+    /// `rows * EPSILON` times the largest element of `A`.
+    pub fn least_squares(&self, b: &[T]) -> Option<Vec<T>> {
+        assert!(
+            self.rows >= self.columns,
+            "The system should not be underdetermined."
+        );
+        assert_eq!(b.len(), self.rows, "Dimension mismatch.");
+        let (n, m) = (self.rows, self.columns);
+        let tolerance = self.tolerance(n);
+        let mut r = self.clone();
+        let mut y = b.to_vec();
+        let two = T::from_f64(2.0);
+
+        // reduce A to R and apply the same reflections to b, so that the
+        // first m elements of y are Q^T b
+        for k in 0..m {
+            let norm = (k..n).fold(T::ZERO, |acc, i| acc + r[(i, k)] * r[(i, k)]);
+            let norm = norm.sqrt();
+            if norm <= tolerance {
+                return None;
+            }
+
+            let mut v: Vec<T> = (k..n).map(|i| r[(i, k)]).collect();
+            v[0] += norm.copysign(r[(k, k)]);
+            let v_squared = v.iter().fold(T::ZERO, |acc, &x| acc + x * x);
+
+            for j in k..m {
+                let s = (k..n).fold(T::ZERO, |acc, i| acc + v[i - k] * r[(i, j)]) * two / v_squared;
+                for i in k..n {
+                    r[(i, j)] -= s * v[i - k];
+                }
+            }
+            let s = (k..n).fold(T::ZERO, |acc, i| acc + v[i - k] * y[i]) * two / v_squared;
+            for i in k..n {
+                y[i] -= s * v[i - k];
+            }
+        }
+
+        // back substitution with the upper triangle of R
+        let mut x = vec![T::ZERO; m];
+        for k in (0..m).rev() {
+            if r[(k, k)].abs() <= tolerance {
+                return None;
+            }
+            let mut value = y[k];
+            for i in k + 1..m {
+                value -= r[(k, i)] * x[i];
+            }
+            x[k] = value / r[(k, k)];
+        }
+        Some(x)
+    }
+
+    /// What rounding can leave of an element that should be zero after `n`
+    /// steps of elimination.
+    fn tolerance(&self, n: usize) -> T {
+        let largest = self.data.iter().fold(T::ZERO, |acc, &x| acc.max(x.abs()));
+        T::from_f64(n as f64) * T::EPSILON * largest
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        if a != b {
+            for j in 0..self.columns {
+                self.data.swap(a * self.columns + j, b * self.columns + j);
+            }
+        }
+    }
+}
+
+impl<T: Scalar> From<&Matrix<T>> for DynamicMatrix<T> {
+    fn from(mat: &Matrix<T>) -> Self {
+        Self::from_rows(&mat.to_rows())
+    }
+}
+
+impl<T> Index<(usize, usize)> for DynamicMatrix<T> {
+    type Output = T;
+
+    /// Index by `(row, column)`.
+    fn index(&self, (i, j): (usize, usize)) -> &T {
+        assert!(
+            i < self.rows && j < self.columns,
+            "Index error: ({}, {}) is out of a {}x{} matrix",
+            i,
+            j,
+            self.rows,
+            self.columns
+        );
+        &self.data[i * self.columns + j]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for DynamicMatrix<T> {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut T {
+        assert!(
+            i < self.rows && j < self.columns,
+            "Index error: ({}, {}) is out of a {}x{} matrix",
+            i,
+            j,
+            self.rows,
+            self.columns
+        );
+        &mut self.data[i * self.columns + j]
+    }
+}
+
+impl<T: Scalar> Mul<&DynamicMatrix<T>> for &DynamicMatrix<T> {
+    type Output = DynamicMatrix<T>;
+
+    fn mul(self, rhs: &DynamicMatrix<T>) -> DynamicMatrix<T> {
+        assert_eq!(self.columns, rhs.rows, "Dimension mismatch.");
+        DynamicMatrix::from_fn(self.rows, rhs.columns, |i, j| {
+            (0..self.columns).fold(T::ZERO, |acc, k| acc + self[(i, k)] * rhs[(k, j)])
+        })
+    }
+}
+
+impl<T: Scalar> Mul<T> for &DynamicMatrix<T> {
+    type Output = DynamicMatrix<T>;
+
+    fn mul(self, rhs: T) -> DynamicMatrix<T> {
+        let data = self.data.iter().map(|&x| x * rhs).collect();
+        DynamicMatrix::new(self.rows, self.columns, data)
+    }
+}
+
+impl<T: Scalar> Add<&DynamicMatrix<T>> for &DynamicMatrix<T> {
+    type Output = DynamicMatrix<T>;
+
+    fn add(self, rhs: &DynamicMatrix<T>) -> DynamicMatrix<T> {
+        assert_eq!(
+            (self.rows, self.columns),
+            (rhs.rows, rhs.columns),
+            "Dimension mismatch."
+        );
+        let data = self
+            .data
+            .iter()
+            .zip(&rhs.data)
+            .map(|(&a, &b)| a + b)
+            .collect();
+        DynamicMatrix::new(self.rows, self.columns, data)
+    }
+}
+
+impl<T: Scalar> Sub<&DynamicMatrix<T>> for &DynamicMatrix<T> {
+    type Output = DynamicMatrix<T>;
+
+    fn sub(self, rhs: &DynamicMatrix<T>) -> DynamicMatrix<T> {
+        assert_eq!(
+            (self.rows, self.columns),
+            (rhs.rows, rhs.columns),
+            "Dimension mismatch."
+        );
+        let data = self
+            .data
+            .iter()
+            .zip(&rhs.data)
+            .map(|(&a, &b)| a - b)
+            .collect();
+        DynamicMatrix::new(self.rows, self.columns, data)
+    }
+}
+
+impl<T: Scalar> Display for DynamicMatrix<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rows: Vec<_> = (0..self.rows).map(|i| self.row(i)).collect();
+        write_matrix(f, &rows, Notation::Decimal)
+    }
+}
+
+impl<T: Scalar> LowerExp for DynamicMatrix<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rows: Vec<_> = (0..self.rows).map(|i| self.row(i)).collect();
+        write_matrix(f, &rows, Notation::Exponent)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DynamicMatrix;
+    use crate::math::Matrix;
+
+    #[test]
+    fn test_dynamic_matrix() {
+        let a = DynamicMatrix::from_rows(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let b = a.transpose();
+        assert_eq!((b.rows(), b.columns()), (3, 2));
+        assert_eq!(b.row(2), &[3.0, 6.0]);
+
+        let product = &a * &b;
+        assert_eq!(
+            product,
+            DynamicMatrix::new(2, 2, vec![14.0, 32.0, 32.0, 77.0])
+        );
+        assert_eq!(a.mul_vector(&[1.0, 0.0, -1.0]), vec![-2.0, -2.0]);
+        assert_eq!(&(&product - &product) + &product, product);
+
+        let mat = Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0);
+        let dynamic = DynamicMatrix::from(&mat);
+        assert_eq!(dynamic.to_string(), mat.to_string());
+        assert_eq!(a.to_string(), "⎡1  2  3⎤\n⎣4  5  6⎦");
+        assert_eq!(
+            DynamicMatrix::from_rows(&[[-1.5, 2.0]]).to_string(),
+            "[-1.5  2.0]"
+        );
+    }
+
+    #[test]
+    fn test_solve() {
+        // needs pivoting, the first pivot is zero
+        let a = DynamicMatrix::<f64>::from_rows(&[
+            [0.0, 2.0, 1.0, -1.0],
+            [1.0, 1.0, 0.0, 2.0],
+            [2.0, -1.0, 3.0, 0.0],
+            [1.0, 0.0, 1.0, 1.0],
+        ]);
+        let x = [1.0, -2.0, 0.5, 3.0];
+        let b = a.mul_vector(&x);
+        let solved = a.solve(&b).unwrap();
+        for (s, x) in solved.iter().zip(x) {
+            assert!((s - x).abs() < 1e-12);
+        }
+
+        let product = &a * &a.inverse().unwrap();
+        let error = &product - &DynamicMatrix::identity(4);
+        assert!(error.as_slice().iter().all(|e| e.abs() < 1e-12));
+
+        let singular = DynamicMatrix::<f64>::from_rows(&[[1.0, 2.0], [2.0, 4.0]]);
+        assert!(singular.solve(&[1.0, 1.0]).is_none());
+        // singular, but rounding leaves a tiny last pivot
+        let singular =
+            DynamicMatrix::<f64>::from_rows(&[[0.1, 0.2, 0.3], [0.4, 0.5, 0.6], [0.7, 0.8, 0.9]]);
+        assert!(singular.inverse().is_none());
+    }
+
+    #[test]
+    fn test_least_squares() {
+        // fit y = 2 + 3x - 0.5x^2 to samples with alternating errors
+        let xs: Vec<f64> = (0..20).map(|i| i as f64 * 0.5).collect();
+        let a = DynamicMatrix::from_fn(xs.len(), 3, |i, j| xs[i].powi(j as i32));
+        let noise = |i: usize| 1e-3 * (-1f64).powi(i as i32);
+        let b: Vec<f64> = (0..xs.len())
+            .map(|i| 2.0 + 3.0 * xs[i] - 0.5 * xs[i] * xs[i] + noise(i))
+            .collect();
+
+        let fit = a.least_squares(&b).unwrap();
+        for (f, expected) in fit.iter().zip([2.0, 3.0, -0.5]) {
+            assert!((f - expected).abs() < 1e-3);
+        }
+
+        // the residual is orthogonal to the columns
+        let residual: Vec<f64> = a
+            .mul_vector(&fit)
+            .iter()
+            .zip(&b)
+            .map(|(p, b)| p - b)
+            .collect();
+        for g in a.transpose().mul_vector(&residual) {
+            assert!(g.abs() < 1e-10);
+        }
+
+        // a square system is solved exactly
+        let square = DynamicMatrix::<f64>::from_rows(&[[2.0, 1.0], [1.0, 3.0]]);
+        let x = square.least_squares(&[3.0, 5.0]).unwrap();
+        assert!((x[0] - 0.8).abs() < 1e-12 && (x[1] - 1.4).abs() < 1e-12);
+
+        let dependent = DynamicMatrix::<f64>::from_rows(&[[1.0, 0.0], [2.0, 0.0], [3.0, 0.0]]);
+        assert!(dependent.least_squares(&[1.0, 2.0, 3.0]).is_none());
+        let column = [0.1, 0.7, 0.3, 1.9];
+        let dependent = DynamicMatrix::from_fn(4, 2, |i, j| column[i] * [1.0, 0.3][j]);
+        assert!(dependent.least_squares(&[1.0, 2.0, 3.0, 4.0]).is_none());
+    }
+}
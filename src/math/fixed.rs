@@ -0,0 +1,213 @@
+use std::{
+    fmt::{Display, LowerExp},
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+use super::Scalar;
+
+/// A Q16.16 fixed-point number: an `i32` counting multiples of `2^-16`, for
+/// targets without a floating point unit.
+///
+/// Addition and subtraction are exact, multiplication and division round to
+/// the nearest multiple of `2^-16`. Every operator panics on overflow, in
+/// release builds as well, instead of wrapping around.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fixed {
+    bits: i32,
+}
+
+impl Fixed {
+    pub const FRACTION_BITS: u32 = 16;
+    pub const MIN: Self = Self::from_bits(i32::MIN);
+    pub const MAX: Self = Self::from_bits(i32::MAX);
+    /// The smallest positive value, `2^-16`.
+    pub const DELTA: Self = Self::from_bits(1);
+
+    pub const fn from_bits(bits: i32) -> Self {
+        Self { bits }
+    }
+
+    pub const fn to_bits(self) -> i32 {
+        self.bits
+    }
+
+    pub const fn from_integer(value: i16) -> Self {
+        Self::from_bits((value as i32) << Self::FRACTION_BITS)
+    }
+}
+
+/// Divide by a power of two, rounding half away from zero.
+fn round_shift(value: i64, shift: u32) -> i64 {
+    let half = 1 << (shift - 1);
+    if value < 0 {
+        -((-value + half) >> shift)
+    } else {
+        (value + half) >> shift
+    }
+}
+
+fn narrow(value: i64) -> Fixed {
+    checked(i32::try_from(value).ok())
+}
+
+fn checked(bits: Option<i32>) -> Fixed {
+    Fixed::from_bits(bits.expect("Fixed overflow."))
+}
+
+impl Scalar for Fixed {
+    const ZERO: Self = Self::from_bits(0);
+    const ONE: Self = Self::from_integer(1);
+
+    /// Round to the nearest multiple of `2^-16`, saturating out of range.
+    fn from_f64(value: f64) -> Self {
+        Self::from_bits((value * (1 << Self::FRACTION_BITS) as f64).round() as i32)
+    }
+
+    /// Exact.
+    fn to_f64(self) -> f64 {
+        self.bits as f64 / (1 << Self::FRACTION_BITS) as f64
+    }
+
+    fn abs(self) -> Self {
+        checked(self.bits.checked_abs())
+    }
+
+    fn min(self, other: Self) -> Self {
+        Ord::min(self, other)
+    }
+
+    fn max(self, other: Self) -> Self {
+        Ord::max(self, other)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        checked(self.bits.checked_neg())
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        checked(self.bits.checked_add(rhs.bits))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        checked(self.bits.checked_sub(rhs.bits))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        narrow(round_shift(
+            self.bits as i64 * rhs.bits as i64,
+            Self::FRACTION_BITS,
+        ))
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+
+    /// Panic if `rhs` is zero.
+    fn div(self, rhs: Self) -> Self {
+        // one extra bit to round with
+        let quotient = ((self.bits as i64) << (Self::FRACTION_BITS + 1)) / rhs.bits as i64;
+        narrow(round_shift(quotient, 1))
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Fixed {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign for Fixed {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign for Fixed {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
+
+impl Display for Fixed {
+    /// Every value is exact in `f64`, so this prints the exact value unless a
+    /// precision is given.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.to_f64(), f)
+    }
+}
+
+impl LowerExp for Fixed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        LowerExp::fmt(&self.to_f64(), f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Fixed;
+    use crate::math::{Scalar, Vector};
+
+    #[test]
+    fn test_fixed() {
+        let a = Fixed::from_f64(1.5);
+        let b = Fixed::from_f64(-0.25);
+        assert_eq!(a.to_bits(), 3 << 15);
+        assert_eq!((a + b).to_f64(), 1.25);
+        assert_eq!((a - b).to_f64(), 1.75);
+        assert_eq!((a * b).to_f64(), -0.375);
+        assert_eq!((a / b).to_f64(), -6.0);
+        assert_eq!(b.abs(), Fixed::from_f64(0.25));
+        assert_eq!(Fixed::from_integer(-3).to_string(), "-3");
+
+        // rounded to the nearest multiple of 2^-16
+        let third = Fixed::ONE / Fixed::from_integer(3);
+        assert_eq!(third.to_bits(), 21845);
+        assert_eq!((-Fixed::ONE / Fixed::from_integer(3)).to_bits(), -21845);
+        assert_eq!((Fixed::DELTA * Fixed::from_f64(0.5)).to_bits(), 1);
+        assert_eq!(Fixed::from_f64(0.1).to_bits(), 6554);
+
+        let x = Vector::new(Fixed::ONE, Fixed::ZERO, Fixed::ZERO);
+        let y = Vector::new(Fixed::ZERO, Fixed::ONE, Fixed::ZERO);
+        assert_eq!(
+            x.cross_product(&y),
+            Vector::new(Fixed::ZERO, Fixed::ZERO, Fixed::ONE)
+        );
+        assert_eq!((x * a + y * b).dot_product(&y), b);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let overflows = |f: fn() -> Fixed| std::panic::catch_unwind(f).is_err();
+        assert!(overflows(|| Fixed::MAX + Fixed::DELTA));
+        assert!(overflows(|| Fixed::MIN - Fixed::DELTA));
+        assert!(overflows(|| -Fixed::MIN));
+        assert!(overflows(|| Fixed::MIN.abs()));
+        assert!(overflows(|| Fixed::MAX * Fixed::from_integer(2)));
+        assert!(overflows(|| Fixed::MAX / Fixed::from_f64(0.5)));
+        assert_eq!(Fixed::MAX - Fixed::MAX + Fixed::MIN, Fixed::MIN);
+    }
+}
@@ -0,0 +1,195 @@
+use std::fmt::{Formatter, Result};
+
+use super::Scalar;
+
+/// The most decimals printed without an explicit precision, so rounding
+/// errors like the tail of `0.1 + 0.2` do not show.
+const MAX_DECIMALS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Notation {
+    Decimal,
+    Exponent,
+}
+
+/// The pretty-printer behind `Display` and `LowerExp` of the matrix types.
+///
+/// - `{}` aligns every column on its decimal point, using as many decimals
+///   as the longest element of the column needs, but at most 6.
+/// - `{:.3}` uses the given number of decimals instead.
+/// - `{:e}` and `{:.3e}` use scientific notation.
+/// - `{:#}` prints everything on one line without padding, e.g.
+///   `[[1, 2], [3, 4]]`.
+///
+/// Matrices are printed between tall brackets, one row per line, and
+/// single-row matrices as `[1  2  3]`.
+pub(super) fn write_matrix<T: Scalar, R: AsRef<[T]>>(
+    f: &mut Formatter<'_>,
+    rows: &[R],
+    notation: Notation,
+) -> Result {
+    let cells = format_cells(f, rows, notation);
+    if f.alternate() {
+        let rows: Vec<_> = cells
+            .iter()
+            .map(|row| format!("[{}]", row.join(", ")))
+            .collect();
+        return write!(f, "[{}]", rows.join(", "));
+    }
+    write_aligned(f, &cells)
+}
+
+/// Like `write_matrix` for a single row, i.e. `[1  2  3]`, or `[1, 2, 3]`
+/// with `{:#}`.
+pub(super) fn write_vector<T: Scalar>(
+    f: &mut Formatter<'_>,
+    elements: &[T],
+    notation: Notation,
+) -> Result {
+    let cells = format_cells(f, &[elements], notation);
+    if f.alternate() {
+        return write!(f, "[{}]", cells[0].join(", "));
+    }
+    write_aligned(f, &cells)
+}
+
+/// Format every element on its own, so the width of each column is known
+/// before anything is written.
+fn format_cells<T: Scalar, R: AsRef<[T]>>(
+    f: &Formatter<'_>,
+    rows: &[R],
+    notation: Notation,
+) -> Vec<Vec<String>> {
+    let column_count = rows.iter().map(|r| r.as_ref().len()).max().unwrap_or(0);
+    let decimals_of = |x: &T| short(x).split('.').nth(1).map_or(0, |d| d.len());
+    let decimals: Vec<usize> = if rows.len() == 1 {
+        // nothing to align in a single row, so keep the elements consistent
+        let max = rows[0].as_ref().iter().map(decimals_of).max().unwrap_or(0);
+        vec![max; column_count]
+    } else {
+        (0..column_count)
+            .map(|j| {
+                rows.iter()
+                    .filter_map(|row| row.as_ref().get(j))
+                    .map(decimals_of)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect()
+    };
+
+    rows.iter()
+        .map(|row| {
+            row.as_ref()
+                .iter()
+                .enumerate()
+                .map(|(j, x)| match (notation, f.precision()) {
+                    (Notation::Decimal, Some(p)) => format!("{:.*}", p, x),
+                    (Notation::Decimal, None) if f.alternate() => short(x),
+                    (Notation::Decimal, None) => format!("{:.*}", decimals[j], x),
+                    (Notation::Exponent, Some(p)) => format!("{:.*e}", p, x),
+                    (Notation::Exponent, None) => format!("{:e}", x),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// At most `MAX_DECIMALS` decimals, without trailing zeros.
+fn short<T: Scalar>(x: &T) -> String {
+    let s = format!("{:.*}", MAX_DECIMALS, x);
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        s
+    }
+}
+
+fn write_aligned(f: &mut Formatter<'_>, cells: &[Vec<String>]) -> Result {
+    if cells.iter().all(|row| row.is_empty()) {
+        return f.write_str("[]");
+    }
+    let column_count = cells.iter().map(|r| r.len()).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..column_count)
+        .map(|j| {
+            cells
+                .iter()
+                .filter_map(|row| row.get(j))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    for (i, row) in cells.iter().enumerate() {
+        let (left, right) = match i {
+            _ if cells.len() == 1 => ('[', ']'),
+            0 => ('⎡', '⎤'),
+            _ if i == cells.len() - 1 => ('⎣', '⎦'),
+            _ => ('⎢', '⎥'),
+        };
+        if i > 0 {
+            f.write_str("\n")?;
+        }
+
+        write!(f, "{}", left)?;
+        for (j, cell) in row.iter().enumerate() {
+            if j > 0 {
+                f.write_str("  ")?;
+            }
+            write!(f, "{:>width$}", cell, width = widths[j])?;
+        }
+        write!(f, "{}", right)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::math::{DynamicMatrix, Matrix, Matrix4, Vector, Vector2i};
+
+    #[test]
+    fn test_matrix_format() {
+        let mat = Matrix::new(0.1 + 0.2, -12.0, 3.0, 4.5, 5.0, -0.25, 100.0, 8.0, 9.0);
+        assert_eq!(
+            mat.to_string(),
+            "⎡  0.3  -12   3.00⎤\n⎢  4.5    5  -0.25⎥\n⎣100.0    8   9.00⎦"
+        );
+        assert_eq!(
+            Matrix::new(1.0 / 3.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 1e-9).to_string(),
+            "⎡0.333333  0  0⎤\n⎢0.000000  2  0⎥\n⎣0.000000  0  0⎦"
+        );
+        assert_eq!(
+            format!("{:.2}", mat),
+            "⎡  0.30  -12.00   3.00⎤\n⎢  4.50    5.00  -0.25⎥\n⎣100.00    8.00   9.00⎦"
+        );
+        assert_eq!(
+            format!("{:.1e}", mat),
+            "⎡3.0e-1  -1.2e1    3.0e0⎤\n⎢ 4.5e0   5.0e0  -2.5e-1⎥\n⎣ 1.0e2   8.0e0    9.0e0⎦"
+        );
+        assert_eq!(
+            format!("{:#}", mat),
+            "[[0.3, -12, 3], [4.5, 5, -0.25], [100, 8, 9]]"
+        );
+        assert_eq!(
+            format!("{:#.1}", Matrix::<f32>::identity()),
+            "[[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]"
+        );
+    }
+
+    #[test]
+    fn test_vector_format() {
+        let v = Vector::new(1.5, -2.0, 10.0);
+        assert_eq!(v.to_string(), "[1.5  -2.0  10.0]");
+        assert_eq!(format!("{:.0}", v), "[2  -2  10]");
+        assert_eq!(format!("{:e}", v), "[1.5e0  -2e0  1e1]");
+        assert_eq!(format!("{:#}", v), "[1.5, -2, 10]");
+        assert_eq!(format!("{:#}", Vector2i::new(3, -4)), "[3, -4]");
+
+        assert_eq!(
+            Matrix4::<f32>::identity().to_string(),
+            "⎡1  0  0  0⎤\n⎢0  1  0  0⎥\n⎢0  0  1  0⎥\n⎣0  0  0  1⎦"
+        );
+        assert_eq!(DynamicMatrix::<f32>::zero(0, 0).to_string(), "[]");
+    }
+}
@@ -0,0 +1,281 @@
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+use super::{Matrix, Matrix4, Real, Scalar, Vector};
+
+/// A position in space, moved by translations.
+///
+/// `Point`, `Direction` and `Normal` all wrap a `Vector`, but each only
+/// allows the operations that make sense for its kind, e.g. two points can
+/// be subtracted but not added.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(transparent)]
+pub struct Point<T = f32>(pub Vector<T>);
+
+/// A displacement between two points, e.g. the direction of a ray. Scaled
+/// and rotated by transforms, but not translated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(transparent)]
+pub struct Direction<T = f32>(pub Vector<T>);
+
+/// A surface normal. Transformed with the inverse transpose, so it stays
+/// perpendicular to the surface.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(transparent)]
+pub struct Normal<T = f32>(pub Vector<T>);
+
+macro_rules! impl_common {
+    ($kind: ident) => {
+        impl<T: Scalar> $kind<T> {
+            pub fn new(x: T, y: T, z: T) -> Self {
+                Self(Vector::new(x, y, z))
+            }
+
+            pub fn vector(&self) -> Vector<T> {
+                self.0
+            }
+        }
+
+        impl<T> From<Vector<T>> for $kind<T> {
+            fn from(vector: Vector<T>) -> Self {
+                Self(vector)
+            }
+        }
+
+        impl<T> From<$kind<T>> for Vector<T> {
+            fn from(value: $kind<T>) -> Self {
+                value.0
+            }
+        }
+    };
+}
+
+impl_common!(Point);
+impl_common!(Direction);
+impl_common!(Normal);
+
+/// Directions and normals can be scaled, negated and measured.
+macro_rules! impl_vector_like {
+    ($kind: ident) => {
+        impl<T: Scalar> $kind<T> {
+            pub fn dot_product(&self, other: &Direction<T>) -> T {
+                self.0.dot_product(&other.0)
+            }
+        }
+
+        impl<T: Real> $kind<T> {
+            pub fn length(&self) -> T {
+                self.0.length()
+            }
+
+            pub fn normalize(&self) -> Self {
+                Self(self.0 / self.0.length())
+            }
+        }
+
+        impl<T: Scalar> Neg for $kind<T> {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+        }
+
+        impl<T: Scalar> Mul<T> for $kind<T> {
+            type Output = Self;
+
+            fn mul(self, rhs: T) -> Self {
+                Self(self.0 * rhs)
+            }
+        }
+
+        impl<T: Scalar> Div<T> for $kind<T> {
+            type Output = Self;
+
+            fn div(self, rhs: T) -> Self {
+                Self(self.0 / rhs)
+            }
+        }
+
+        impl<T: Scalar> Add for $kind<T> {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl<T: Scalar> Sub for $kind<T> {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0 - rhs.0)
+            }
+        }
+    };
+}
+
+impl_vector_like!(Direction);
+impl_vector_like!(Normal);
+
+impl<T: Scalar> Point<T> {
+    pub fn origin() -> Self {
+        Self::new(T::ZERO, T::ZERO, T::ZERO)
+    }
+}
+
+impl<T: Real> Point<T> {
+    pub fn distance(&self, other: &Self) -> T {
+        (*self - *other).length()
+    }
+}
+
+impl<T: Scalar> Direction<T> {
+    pub fn cross_product(&self, other: &Self) -> Self {
+        Self(self.0.cross_product(&other.0))
+    }
+}
+
+impl<T: Scalar> Normal<T> {
+    /// Flip the normal to the hemisphere of `direction`.
+    pub fn face_forward(&self, direction: &Direction<T>) -> Self {
+        if self.dot_product(direction) < T::ZERO {
+            -*self
+        } else {
+            *self
+        }
+    }
+}
+
+impl<T: Scalar> Sub for Point<T> {
+    type Output = Direction<T>;
+
+    fn sub(self, rhs: Self) -> Direction<T> {
+        Direction(self.0 - rhs.0)
+    }
+}
+
+impl<T: Scalar> Add<Direction<T>> for Point<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Direction<T>) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl<T: Scalar> Sub<Direction<T>> for Point<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Direction<T>) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl<T: Scalar> AddAssign<Direction<T>> for Point<T> {
+    fn add_assign(&mut self, rhs: Direction<T>) {
+        self.0 += rhs.0;
+    }
+}
+
+impl<T: Scalar> SubAssign<Direction<T>> for Point<T> {
+    fn sub_assign(&mut self, rhs: Direction<T>) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl<T: Scalar> Mul<Point<T>> for &Matrix<T> {
+    type Output = Point<T>;
+
+    fn mul(self, rhs: Point<T>) -> Point<T> {
+        Point(self * rhs.0)
+    }
+}
+
+impl<T: Scalar> Mul<Direction<T>> for &Matrix<T> {
+    type Output = Direction<T>;
+
+    fn mul(self, rhs: Direction<T>) -> Direction<T> {
+        Direction(self * rhs.0)
+    }
+}
+
+impl<T: Real> Mul<Normal<T>> for &Matrix<T> {
+    type Output = Normal<T>;
+
+    /// Transform with the inverse transpose, see `Matrix::transform_normal`.
+    fn mul(self, rhs: Normal<T>) -> Normal<T> {
+        Normal(self.transform_normal(&rhs.0))
+    }
+}
+
+impl<T: Scalar> Mul<Point<T>> for &Matrix4<T> {
+    type Output = Point<T>;
+
+    fn mul(self, rhs: Point<T>) -> Point<T> {
+        Point(self.transform_point(&rhs.0))
+    }
+}
+
+impl<T: Scalar> Mul<Direction<T>> for &Matrix4<T> {
+    type Output = Direction<T>;
+
+    fn mul(self, rhs: Direction<T>) -> Direction<T> {
+        Direction(self.transform_direction(&rhs.0))
+    }
+}
+
+impl<T: Real> Mul<Normal<T>> for &Matrix4<T> {
+    type Output = Normal<T>;
+
+    /// Transform with the inverse transpose, see `Matrix::transform_normal`.
+    fn mul(self, rhs: Normal<T>) -> Normal<T> {
+        Normal(self.transform_normal(&rhs.0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Direction, Normal, Point};
+    use crate::math::{Matrix, Matrix4, Vector};
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Point::new(1.0, 2.0, 3.0);
+        let b = Point::new(4.0, 6.0, 3.0);
+
+        let d = b - a;
+        assert_eq!(d, Direction::new(3.0, 4.0, 0.0));
+        assert_eq!(d.length(), 5.0);
+        assert_eq!(a.distance(&b), 5.0);
+        assert_eq!(a + d, b);
+
+        let mut c = b;
+        c -= d * 2.0;
+        assert_eq!(c, Point::new(-2.0, -2.0, 3.0));
+
+        let n = Normal::new(0.0, 0.0, 1.0);
+        assert_eq!(n.face_forward(&Direction::new(0.0, 1.0, -1.0)), -n);
+    }
+
+    #[test]
+    fn test_transform() {
+        let mat = Matrix4::translation(&Vector::new(1.0, 0.0, 0.0))
+            * Matrix4::scale(&Vector::new(2.0, 1.0, 1.0));
+
+        assert_eq!(&mat * Point::new(1.0, 1.0, 0.0), Point::new(3.0, 1.0, 0.0));
+        assert_eq!(
+            &mat * Direction::new(1.0, 1.0, 0.0),
+            Direction::new(2.0, 1.0, 0.0)
+        );
+
+        // the plane x + y = 0 becomes x + 2y = 0 after the scale
+        let n = &mat * Normal::new(1.0, 1.0, 0.0);
+        let expected = Normal::new(1.0, 2.0, 0.0).normalize();
+        assert!((n - expected).length() < 1e-6);
+
+        let linear = Matrix::from_diagonal(2.0, 1.0, 1.0);
+        assert_eq!(&linear * Normal::new(1.0, 1.0, 0.0), n);
+    }
+}
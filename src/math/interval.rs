@@ -0,0 +1,429 @@
+use std::{
+    fmt::Display,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use super::{QuadraticResult, Real};
+
+/// A closed interval `[lo, hi]` of real numbers. Every operation rounds
+/// its bounds outward by one ULP, which covers the at most half an ULP error
+/// of the correctly rounded floating point operations, so the exact result
+/// is always inside.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        try_from = "RawInterval<T>",
+        bound(deserialize = "T: serde::Deserialize<'de> + PartialOrd + Display")
+    )
+)]
+pub struct Interval<T = f32> {
+    lo: T,
+    hi: T,
+}
+
+/// What is deserialized before the bounds are checked as in `new`.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawInterval<T> {
+    lo: T,
+    hi: T,
+}
+
+#[cfg(feature = "serde")]
+impl<T: PartialOrd + Display> TryFrom<RawInterval<T>> for Interval<T> {
+    type Error = String;
+
+    fn try_from(raw: RawInterval<T>) -> Result<Self, String> {
+        if raw.lo <= raw.hi {
+            Ok(Self {
+                lo: raw.lo,
+                hi: raw.hi,
+            })
+        } else {
+            Err(format!("Invalid interval: [{}, {}]", raw.lo, raw.hi))
+        }
+    }
+}
+
+impl<T: Real> Interval<T> {
+    /// Panic if `lo > hi` or either is NaN.
+    pub fn new(lo: T, hi: T) -> Self {
+        assert!(lo <= hi, "Invalid interval: [{}, {}]", lo, hi);
+        Self { lo, hi }
+    }
+
+    /// A single number, which is exact.
+    pub fn point(value: T) -> Self {
+        Self::new(value, value)
+    }
+
+    /// `value ± error`, rounded outward.
+    pub fn with_error(value: T, error: T) -> Self {
+        Self::new((value - error).next_down(), (value + error).next_up())
+    }
+
+    /// The whole real line, e.g. the result of dividing by an interval
+    /// containing zero.
+    pub fn entire() -> Self {
+        let infinity = T::ONE / T::ZERO;
+        Self::new(-infinity, infinity)
+    }
+
+    pub fn lo(&self) -> T {
+        self.lo
+    }
+
+    pub fn hi(&self) -> T {
+        self.hi
+    }
+
+    pub fn midpoint(&self) -> T {
+        (self.lo + self.hi) / T::from_f64(2.0)
+    }
+
+    pub fn width(&self) -> T {
+        self.hi - self.lo
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.lo <= value && value <= self.hi
+    }
+
+    /// The smallest interval containing both.
+    pub fn hull(&self, other: &Self) -> Self {
+        Self::new(self.lo.min(other.lo), self.hi.max(other.hi))
+    }
+
+    /// Tighter than `x * x` when the interval contains zero, because both
+    /// factors are the same number.
+    pub fn square(&self) -> Self {
+        let (lo, hi) = (self.lo.abs(), self.hi.abs());
+        let (lo, hi) = if lo > hi { (hi, lo) } else { (lo, hi) };
+        if self.contains(T::ZERO) {
+            Self::new(T::ZERO, (hi * hi).next_up())
+        } else {
+            Self::new((lo * lo).next_down().max(T::ZERO), (hi * hi).next_up())
+        }
+    }
+
+    /// The square root of the non-negative part. Return `None` if the
+    /// interval is entirely negative.
+    pub fn sqrt(&self) -> Option<Self> {
+        if self.hi < T::ZERO {
+            return None;
+        }
+        let lo = if self.lo <= T::ZERO {
+            T::ZERO
+        } else {
+            self.lo.sqrt().next_down().max(T::ZERO)
+        };
+        Some(Self::new(lo, self.hi.sqrt().next_up()))
+    }
+}
+
+impl<T: Real> From<T> for Interval<T> {
+    fn from(value: T) -> Self {
+        Self::point(value)
+    }
+}
+
+impl<T: Real> Neg for Interval<T> {
+    type Output = Self;
+
+    /// Exact, no rounding is needed.
+    fn neg(self) -> Self {
+        Self::new(-self.hi, -self.lo)
+    }
+}
+
+impl<T: Real> Add for Interval<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new((self.lo + rhs.lo).next_down(), (self.hi + rhs.hi).next_up())
+    }
+}
+
+impl<T: Real> Sub for Interval<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new((self.lo - rhs.hi).next_down(), (self.hi - rhs.lo).next_up())
+    }
+}
+
+impl<T: Real> Mul for Interval<T> {
+    type Output = Self;
+
+    /// `0 * ∞` counts as zero, as a bound at infinity is never reached.
+    fn mul(self, rhs: Self) -> Self {
+        let product = |a: T, b: T| {
+            if a == T::ZERO || b == T::ZERO {
+                T::ZERO
+            } else {
+                a * b
+            }
+        };
+        let products = [
+            product(self.lo, rhs.lo),
+            product(self.lo, rhs.hi),
+            product(self.hi, rhs.lo),
+            product(self.hi, rhs.hi),
+        ];
+        bounds(products)
+    }
+}
+
+impl<T: Real> Div for Interval<T> {
+    type Output = Self;
+
+    /// Return the whole real line if `rhs` contains zero. `∞ / ∞` counts as
+    /// zero, like `∞ * (1 / ∞)` in `mul`.
+    fn div(self, rhs: Self) -> Self {
+        if rhs.contains(T::ZERO) {
+            return Self::entire();
+        }
+        let quotient = |a: T, b: T| {
+            if a.is_finite() || b.is_finite() {
+                a / b
+            } else {
+                T::ZERO
+            }
+        };
+        let quotients = [
+            quotient(self.lo, rhs.lo),
+            quotient(self.lo, rhs.hi),
+            quotient(self.hi, rhs.lo),
+            quotient(self.hi, rhs.hi),
+        ];
+        bounds(quotients)
+    }
+}
+
+impl<T: Real> Mul<T> for Interval<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self {
+        self * Self::point(rhs)
+    }
+}
+
+impl<T: Real> Display for Interval<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[")?;
+        Display::fmt(&self.lo, f)?;
+        write!(f, ", ")?;
+        Display::fmt(&self.hi, f)?;
+        write!(f, "]")
+    }
+}
+
+/// The rounded-outward hull of the candidates for the bounds of a product
+/// or quotient.
+fn bounds<T: Real>(candidates: [T; 4]) -> Interval<T> {
+    let lo = candidates.iter().fold(candidates[0], |a, &b| a.min(b));
+    let hi = candidates.iter().fold(candidates[0], |a, &b| a.max(b));
+    Interval::new(lo.next_down(), hi.next_up())
+}
+
+/// Solve `a x^2 + b x + c = 0` with interval coefficients, e.g. the
+/// coefficients of a ray-sphere intersection together with their rounding
+/// error. Every real root of every quadratic with coefficients inside the
+/// intervals is inside one of the returned intervals:
+///
+/// - `Roots` if the discriminant is certainly positive. The two intervals
+///   are ordered by their lower bound and may overlap.
+/// - `NoRealSolution` if the discriminant is certainly negative.
+/// - `DoubleRoot` enclosing every possible root otherwise, even though
+///   there may be none. Hit tests stay watertight that way, and can avoid
+///   false hits by checking that the whole interval is in range.
+///
+/// As in `quadratic_equation`, an exactly zero `a` is a linear equation.
+pub fn quadratic_equation_interval<T: Real>(
+    a: Interval<T>,
+    b: Interval<T>,
+    c: Interval<T>,
+) -> QuadraticResult<Interval<T>> {
+    let zero = Interval::point(T::ZERO);
+    if a == zero {
+        return if b == zero {
+            if c.contains(T::ZERO) {
+                QuadraticResult::AllNumbers
+            } else {
+                QuadraticResult::NoRealSolution
+            }
+        } else {
+            QuadraticResult::SingleRoot(-c / b)
+        };
+    }
+
+    let discriminant = b.square() - a * c * T::from_f64(4.0);
+    let Some(root) = discriminant.sqrt() else {
+        return QuadraticResult::NoRealSolution;
+    };
+
+    // numerical stability, any sign gives a valid enclosure but this one
+    // avoids cancellation for the midpoint
+    let q = if b.midpoint() < T::ZERO {
+        (b - root) * T::from_f64(-0.5)
+    } else {
+        (b + root) * T::from_f64(-0.5)
+    };
+    let (x1, x2) = (q / a, c / q);
+
+    if discriminant.lo() > T::ZERO {
+        if x1.lo() <= x2.lo() {
+            QuadraticResult::Roots(x1, x2)
+        } else {
+            QuadraticResult::Roots(x2, x1)
+        }
+    } else {
+        QuadraticResult::DoubleRoot(x1.hull(&x2))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::{quadratic_equation_interval, Interval};
+    use crate::math::QuadraticResult;
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Interval::new(1.0, 2.0);
+        let b = Interval::new(-3.0, 0.5);
+
+        let sum = a + b;
+        assert!(sum.lo() < -2.0 && sum.lo() > -2.0 - 1e-9 && sum.hi() > 2.5);
+        assert!((a - b).contains(5.0) && (a - b).contains(0.5));
+        assert!((a * b).contains(-6.0) && (a * b).contains(1.0));
+        assert!((a * b).lo() >= -6.0 - 1e-9);
+        assert_eq!(a / b, Interval::entire());
+        assert!((b / a).contains(-3.0) && (b / a).contains(0.5));
+        assert_eq!((-a).hi(), -1.0);
+
+        assert_eq!(b.square().lo(), 0.0);
+        assert!(b.square().contains(9.0));
+        assert!((b * b).contains(-1.0) && !b.square().contains(-1.0));
+        assert!(Interval::new(4.0, 9.0).sqrt().unwrap().contains(2.0));
+        assert!(Interval::new(-4.0, -1.0).sqrt().is_none());
+
+        // 0.1 is not representable, but the interval still contains 0.3
+        let tenth = Interval::with_error(0.1f32, 0.0);
+        let sum = tenth + tenth + tenth;
+        assert!(sum.contains(0.3) && sum.width() < 1e-6);
+        assert_eq!(sum.to_string(), format!("[{}, {}]", sum.lo(), sum.hi()));
+    }
+
+    #[test]
+    fn test_infinite() {
+        let entire = Interval::<f64>::entire();
+        let zero = Interval::point(0.0);
+        assert!((zero * entire).contains(0.0) && (zero * entire).width() < 1e-300);
+        assert!((Interval::new(0.0, 1.0) * entire).contains(-1e300));
+        assert_eq!(entire * entire, entire);
+
+        let positive = Interval::new(1.0, f64::INFINITY);
+        assert!((positive / positive).contains(0.5) && (positive / positive).contains(2.0));
+        assert!((entire / positive).contains(-1e300) && (entire / positive).contains(1e300));
+    }
+
+    #[test]
+    fn test_enclosure() {
+        // the interval result of random expressions contains the f64 result
+        // of the same expression on f32 inputs
+        let mut rng = StdRng::seed_from_u64(18);
+        for _ in 0..10000 {
+            let [x, y, z] = [(); 3].map(|_| rng.gen_range(-10.0f32..10.0));
+            let [ix, iy, iz] = [x, y, z].map(Interval::point);
+            let exact = (x as f64 * y as f64 - z as f64) / (1.0 + (x as f64).powi(2));
+            let interval = (ix * iy - iz) / (Interval::point(1.0) + ix.square());
+            assert!(
+                interval.lo() as f64 <= exact && exact <= interval.hi() as f64,
+                "{} not in {}",
+                exact,
+                interval
+            );
+        }
+    }
+
+    #[test]
+    fn test_quadratic_interval() {
+        let exact = Interval::point;
+        match quadratic_equation_interval(exact(1.0), exact(-1.0), exact(-6.0)) {
+            QuadraticResult::Roots(a, b) => {
+                assert!(a.contains(-2.0) && b.contains(3.0));
+                assert!(a.width() < 1e-5 && b.width() < 1e-5);
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(
+            quadratic_equation_interval(exact(1.0), exact(2.0), exact(5.0)),
+            QuadraticResult::NoRealSolution
+        );
+        match quadratic_equation_interval(exact(0.0), exact(2.0), exact(-1.0)) {
+            QuadraticResult::SingleRoot(x) => assert!(x.contains(0.5)),
+            other => panic!("{:?}", other),
+        }
+
+        // a ray grazing a unit sphere: the discriminant is uncertain, so the
+        // roots are enclosed by a double root
+        let c = Interval::with_error(1.0f32 - 1e-8, 1e-7);
+        match quadratic_equation_interval(exact(1.0), exact(-2.0), c) {
+            QuadraticResult::DoubleRoot(x) => assert!(x.contains(1.0)),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ray_sphere() {
+        // rays from random origins towards a unit sphere, many of them
+        // grazing it: the intervals always contain the roots, which are
+        // computed from the same f32 inputs in f64
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..10000 {
+            let origin = [(); 3].map(|_| rng.gen_range(-100.0f32..100.0));
+            let theta = rng.gen_range(0.0f32..std::f32::consts::TAU);
+            let z = rng.gen_range(-1.0f32..1.0);
+            let r = (1.0 - z * z).sqrt();
+            let target = [r * theta.cos(), r * theta.sin(), z];
+            let direction = [0, 1, 2].map(|i| target[i] - origin[i]);
+
+            let dot = |u: [f32; 3], v: [f32; 3]| {
+                (0..3).fold(Interval::point(0.0), |acc, i| {
+                    acc + Interval::point(u[i]) * Interval::point(v[i])
+                })
+            };
+            let a = dot(direction, direction);
+            let b = dot(origin, direction) * 2.0;
+            let c = dot(origin, origin) - Interval::point(1.0);
+
+            let dot64 =
+                |u: [f32; 3], v: [f32; 3]| (0..3).map(|i| u[i] as f64 * v[i] as f64).sum::<f64>();
+            let (a64, b64, c64) = (
+                dot64(direction, direction),
+                2.0 * dot64(origin, direction),
+                dot64(origin, origin) - 1.0,
+            );
+            let discriminant = b64 * b64 - 4.0 * a64 * c64;
+            if discriminant < 0.0 {
+                continue;
+            }
+            let roots = [-1.0, 1.0].map(|sign| (-b64 + sign * discriminant.sqrt()) / (2.0 * a64));
+
+            let result = quadratic_equation_interval(a, b, c);
+            for root in roots {
+                let inside = |t: Interval<f32>| t.lo() as f64 <= root && root <= t.hi() as f64;
+                let found = match result {
+                    QuadraticResult::Roots(t0, t1) => inside(t0) || inside(t1),
+                    QuadraticResult::DoubleRoot(t) => inside(t),
+                    _ => false,
+                };
+                assert!(found, "{} not in {:?}", root, result);
+            }
+        }
+    }
+}
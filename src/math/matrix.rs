@@ -1,28 +1,26 @@
-use std::{fmt::Display, ops::Mul};
+use std::{
+    fmt::{Display, Formatter, LowerExp},
+    ops::{Add, Mul, Sub},
+};
 
-use super::Vector;
+use super::{
+    format::{write_matrix, Notation},
+    Real, Scalar, Vector,
+};
 
 /// Just for test purposes, the performance of this `Matrix` struct
 /// may be extremely low.
-#[derive(Debug)]
-pub struct Matrix {
-    row1: Vector,
-    row2: Vector,
-    row3: Vector,
-}
-
-impl Matrix {
-    pub fn new(
-        x11: f32,
-        x12: f32,
-        x13: f32,
-        x21: f32,
-        x22: f32,
-        x23: f32,
-        x31: f32,
-        x32: f32,
-        x33: f32,
-    ) -> Self {
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Matrix<T = f32> {
+    row1: Vector<T>,
+    row2: Vector<T>,
+    row3: Vector<T>,
+}
+
+impl<T: Scalar> Matrix<T> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(x11: T, x12: T, x13: T, x21: T, x22: T, x23: T, x31: T, x32: T, x33: T) -> Self {
         Self::from_vectors(
             Vector::new(x11, x12, x13),
             Vector::new(x21, x22, x23),
@@ -30,10 +28,63 @@ impl Matrix {
         )
     }
 
-    pub fn from_vectors(row1: Vector, row2: Vector, row3: Vector) -> Self {
+    pub fn from_vectors(row1: Vector<T>, row2: Vector<T>, row3: Vector<T>) -> Self {
         Self { row1, row2, row3 }
     }
 
+    pub fn from_columns(col1: Vector<T>, col2: Vector<T>, col3: Vector<T>) -> Self {
+        Self::from_vectors(col1, col2, col3).transpose()
+    }
+
+    pub fn from_rows(rows: [[T; 3]; 3]) -> Self {
+        Self::from_vectors(rows[0].into(), rows[1].into(), rows[2].into())
+    }
+
+    pub fn zero() -> Self {
+        let zero = Vector::new(T::ZERO, T::ZERO, T::ZERO);
+        Self::from_vectors(zero, zero, zero)
+    }
+
+    pub fn identity() -> Self {
+        Self::from_diagonal(T::ONE, T::ONE, T::ONE)
+    }
+
+    pub fn from_diagonal(x11: T, x22: T, x33: T) -> Self {
+        Self::new(
+            x11,
+            T::ZERO,
+            T::ZERO,
+            T::ZERO,
+            x22,
+            T::ZERO,
+            T::ZERO,
+            T::ZERO,
+            x33,
+        )
+    }
+
+    /// Get the row at `index`, which should be in [0, 2].
+    pub fn row(&self, index: usize) -> Vector<T> {
+        match index {
+            0 => self.row1,
+            1 => self.row2,
+            2 => self.row3,
+            _ => panic!(
+                "Index error: row should be in [0, 2], current index is {}",
+                index
+            ),
+        }
+    }
+
+    /// Get the column at `index`, which should be in [0, 2].
+    pub fn column(&self, index: usize) -> Vector<T> {
+        Vector::new(self.row1[index], self.row2[index], self.row3[index])
+    }
+
+    pub fn to_rows(&self) -> [[T; 3]; 3] {
+        [self.row1.into(), self.row2.into(), self.row3.into()]
+    }
+
     pub fn transpose(&self) -> Self {
         Self::new(
             self.row1.x,
@@ -47,13 +98,89 @@ impl Matrix {
             self.row3.z,
         )
     }
+
+    pub fn trace(&self) -> T {
+        self.row1.x + self.row2.y + self.row3.z
+    }
+
+    /// Expand the determinant along the first row, which is the dot product
+    /// of the first row and the cross product of the other two.
+    pub fn determinant(&self) -> T {
+        self.row1.dot_product(&self.row2.cross_product(&self.row3))
+    }
+
+    /// The transpose of the cofactor matrix. The columns of the adjugate are
+    /// the cross products of pairs of rows.
+    pub fn adjugate(&self) -> Self {
+        Self::from_columns(
+            self.row2.cross_product(&self.row3),
+            self.row3.cross_product(&self.row1),
+            self.row1.cross_product(&self.row2),
+        )
+    }
+}
+
+impl<T: Real> Matrix<T> {
+    /// Get the inverse through `adjugate / determinant`. Return `None` if
+    /// the matrix is singular up to rounding, i.e. the determinant is at most
+    /// `3 * EPSILON` times the product of the row or of the column norms.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        let rows = [0, 1, 2].map(|i| self.row(i).into());
+        if is_negligible_determinant(det, &rows) {
+            return None;
+        }
+
+        Some(self.adjugate() * (T::ONE / det))
+    }
+
+    /// Transform a surface normal with the inverse transpose, so it stays
+    /// perpendicular to transformed tangents. The cofactor matrix is used
+    /// instead of the inverse, which only differs by the determinant, so the
+    /// result is renormalized and flipped if the determinant is negative.
+    ///
+    /// A singular matrix that squashes the surface into a plane still gives
+    /// the normal of that plane, though which side it points to is
+    /// arbitrary. If the surface is squashed into a line or a point there is
+    /// no normal, and the zero vector is returned.
+    pub fn transform_normal(&self, normal: &Vector<T>) -> Vector<T> {
+        let cofactor = self.adjugate().transpose();
+        let n = &cofactor * normal;
+        let length = n.length();
+        if length == T::ZERO {
+            return n;
+        }
+        let det = self.determinant();
+        let sign = if det < T::ZERO { -T::ONE } else { T::ONE };
+        n * (sign / length)
+    }
 }
 
-impl Mul<&Matrix> for &Matrix {
-    type Output = Matrix;
+/// Whether `det` is within rounding of zero: at most `n * EPSILON` times
+/// the product of the row norms or of the column norms, which bound the
+/// determinant. The smaller one is used, so e.g. a large translation does
+/// not make a matrix look singular.
+pub(crate) fn is_negligible_determinant<T: Real, const N: usize>(
+    det: T,
+    rows: &[[T; N]; N],
+) -> bool {
+    let row_norms = rows.iter().fold(T::ONE, |acc, row| {
+        acc * row.iter().fold(T::ZERO, |sum, &x| sum + x * x).sqrt()
+    });
+    let column_norms = (0..N).fold(T::ONE, |acc, j| {
+        acc * rows
+            .iter()
+            .fold(T::ZERO, |sum, row| sum + row[j] * row[j])
+            .sqrt()
+    });
+    det.abs() <= T::from_f64(N as f64) * T::EPSILON * row_norms.min(column_norms)
+}
+
+impl<T: Scalar> Mul<&Matrix<T>> for &Matrix<T> {
+    type Output = Matrix<T>;
 
     /// Matrix Multiplication
-    fn mul(self, rhs: &Matrix) -> Matrix {
+    fn mul(self, rhs: &Matrix<T>) -> Matrix<T> {
         Matrix {
             row1: Vector::new(
                 self.row1.x * rhs.row1.x + self.row1.y * rhs.row2.x + self.row1.z * rhs.row3.x,
@@ -73,120 +200,187 @@ impl Mul<&Matrix> for &Matrix {
         }
     }
 }
-impl Mul<Matrix> for &Matrix {
-    type Output = Matrix;
+impl<T: Scalar> Mul<Matrix<T>> for &Matrix<T> {
+    type Output = Matrix<T>;
 
     /// Matrix Multiplication
-    fn mul(self, rhs: Matrix) -> Matrix {
+    fn mul(self, rhs: Matrix<T>) -> Matrix<T> {
         self * &rhs
     }
 }
 
-impl Display for Matrix {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let elements = [
-            self.row1.x,
-            self.row1.y,
-            self.row1.z,
-            self.row2.x,
-            self.row2.y,
-            self.row2.z,
-            self.row3.x,
-            self.row3.y,
-            self.row3.z,
-        ];
-
-        let mut int_len = 0;
-        let mut float_len = 0;
-        let mut sign = 0;
-        let mut point = 0;
-        for each in elements {
-            let f = format!("{}", each);
-            let mut tmp = f.split(".");
-
-            if let Some(int) = tmp.next() {
-                if int.contains('-') {
-                    sign = 1;
-                    if int.len() - 1 > int_len {
-                        int_len = int.len() - 1;
-                    }
-                } else if int.len() > int_len {
-                    int_len = int.len();
-                }
-            }
-            if let Some(float) = tmp.next() {
-                if float.len() > float_len {
-                    point = 1;
-                    float_len = float.len();
-                }
-            }
-        }
+impl<T: Scalar> Mul<Matrix<T>> for Matrix<T> {
+    type Output = Matrix<T>;
+
+    /// Matrix Multiplication
+    fn mul(self, rhs: Matrix<T>) -> Matrix<T> {
+        &self * &rhs
+    }
+}
+
+impl<T: Scalar> Mul<Vector<T>> for &Matrix<T> {
+    type Output = Vector<T>;
 
-        let total_len = int_len + float_len + point + sign;
-        f.write_fmt(format_args!(
-            "⎡{number:>total$.float$}  ",
-            number = self.row1.x,
-            total = total_len,
-            float = float_len
-        ))?;
-        f.write_fmt(format_args!(
-            "{number:>total$.float$}  ",
-            number = self.row1.y,
-            total = total_len,
-            float = float_len
-        ))?;
-        f.write_fmt(format_args!(
-            "{number:>total$.float$}⎤\n",
-            number = self.row1.z,
-            total = total_len,
-            float = float_len
-        ))?;
-        f.write_fmt(format_args!(
-            "⎢{number:>total$.float$}  ",
-            number = self.row2.x,
-            total = total_len,
-            float = float_len
-        ))?;
-        f.write_fmt(format_args!(
-            "{number:>total$.float$}  ",
-            number = self.row2.y,
-            total = total_len,
-            float = float_len
-        ))?;
-        f.write_fmt(format_args!(
-            "{number:>total$.float$}⎥\n",
-            number = self.row2.z,
-            total = total_len,
-            float = float_len
-        ))?;
-        f.write_fmt(format_args!(
-            "⎣{number:>total$.float$}  ",
-            number = self.row3.x,
-            total = total_len,
-            float = float_len
-        ))?;
-        f.write_fmt(format_args!(
-            "{number:>total$.float$}  ",
-            number = self.row3.y,
-            total = total_len,
-            float = float_len
-        ))?;
-        f.write_fmt(format_args!(
-            "{number:>total$.float$}⎦",
-            number = self.row3.z,
-            total = total_len,
-            float = float_len
-        ))
+    /// Multiply the matrix by a column vector.
+    fn mul(self, rhs: Vector<T>) -> Vector<T> {
+        Vector::new(
+            self.row1.dot_product(&rhs),
+            self.row2.dot_product(&rhs),
+            self.row3.dot_product(&rhs),
+        )
+    }
+}
+
+impl<T: Scalar> Mul<&Vector<T>> for &Matrix<T> {
+    type Output = Vector<T>;
+
+    /// Multiply the matrix by a column vector.
+    fn mul(self, rhs: &Vector<T>) -> Vector<T> {
+        self * *rhs
+    }
+}
+
+impl<T: Scalar> Mul<Vector<T>> for Matrix<T> {
+    type Output = Vector<T>;
+
+    /// Multiply the matrix by a column vector.
+    fn mul(self, rhs: Vector<T>) -> Vector<T> {
+        &self * rhs
+    }
+}
+
+impl<T: Scalar> Mul<T> for Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn mul(self, rhs: T) -> Matrix<T> {
+        Matrix::from_vectors(self.row1 * rhs, self.row2 * rhs, self.row3 * rhs)
+    }
+}
+
+impl<T: Scalar> Mul<T> for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn mul(self, rhs: T) -> Matrix<T> {
+        Matrix::from_vectors(self.row1 * rhs, self.row2 * rhs, self.row3 * rhs)
+    }
+}
+
+impl<T: Scalar> Add<&Matrix<T>> for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn add(self, rhs: &Matrix<T>) -> Matrix<T> {
+        Matrix::from_vectors(
+            self.row1 + rhs.row1,
+            self.row2 + rhs.row2,
+            self.row3 + rhs.row3,
+        )
+    }
+}
+
+impl<T: Scalar> Add<Matrix<T>> for Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn add(self, rhs: Matrix<T>) -> Matrix<T> {
+        &self + &rhs
+    }
+}
+
+impl<T: Scalar> Sub<&Matrix<T>> for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn sub(self, rhs: &Matrix<T>) -> Matrix<T> {
+        Matrix::from_vectors(
+            self.row1 - rhs.row1,
+            self.row2 - rhs.row2,
+            self.row3 - rhs.row3,
+        )
+    }
+}
+
+impl<T: Scalar> Sub<Matrix<T>> for Matrix<T> {
+    type Output = Matrix<T>;
+
+    fn sub(self, rhs: Matrix<T>) -> Matrix<T> {
+        &self - &rhs
+    }
+}
+
+impl<T: Scalar> Display for Matrix<T> {
+    /// See the `format` module for the supported options.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_matrix(f, &self.to_rows(), Notation::Decimal)
+    }
+}
+
+impl<T: Scalar> LowerExp for Matrix<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_matrix(f, &self.to_rows(), Notation::Exponent)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::Matrix;
+    use crate::assert_approx_eq;
+    use crate::math::{Tolerance, Vector};
 
     #[test]
     fn test_matrix() {
         let mat = Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0);
         println!("{}", &mat * mat.transpose());
+        assert_approx_eq!(
+            &mat * mat.transpose(),
+            Matrix::new(14.0, 32.0, 50.0, 32.0, 77.0, 122.0, 50.0, 122.0, 194.0),
+            Tolerance::ulps(0)
+        );
+    }
+
+    #[test]
+    fn test_matrix_f64() {
+        let mat = Matrix::<f64>::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0);
+        println!("{}", &mat * mat.transpose());
+    }
+
+    #[test]
+    fn test_inverse() {
+        let mat = Matrix::<f64>::new(1.0, 2.0, 3.0, 0.0, 1.0, 4.0, 5.0, 6.0, 0.0);
+        assert_eq!(mat.determinant(), 1.0);
+        assert_eq!(mat.trace(), 2.0);
+
+        let inv = mat.inverse().unwrap();
+        assert_eq!(
+            inv,
+            Matrix::new(-24.0, 18.0, 5.0, 20.0, -15.0, -4.0, -5.0, 4.0, 1.0)
+        );
+        assert_eq!(&mat * &inv, Matrix::identity());
+        assert_eq!(mat.adjugate(), &inv * mat.determinant());
+
+        let singular = Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0);
+        assert!(singular.inverse().is_none());
+        // rank 2, but the rounded determinant is about 1.7e-17
+        let singular = Matrix::<f64>::new(0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9);
+        assert_ne!(singular.determinant(), 0.0);
+        assert!(singular.inverse().is_none());
+        // tiny but well conditioned
+        assert!(Matrix::<f64>::from_diagonal(1e-100, 1e-100, 1e-100)
+            .inverse()
+            .is_some());
+    }
+
+    #[test]
+    fn test_matrix_vector() {
+        let mat = Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0);
+        assert_eq!(mat.column(1), Vector::new(2.0, 5.0, 8.0));
+        assert_eq!(mat.row(2), Vector::new(7.0, 8.0, 9.0));
+        assert_eq!(
+            Matrix::from_columns(mat.column(0), mat.column(1), mat.column(2)),
+            mat
+        );
+        assert_eq!(
+            &mat * Vector::new(1.0, 0.0, -1.0),
+            Vector::new(-2.0, -2.0, -2.0)
+        );
+        assert_eq!(&mat + &mat, mat.clone() * 2.0);
+        assert_eq!(mat.clone() - mat, Matrix::zero());
     }
 }
@@ -0,0 +1,444 @@
+use std::{
+    fmt::{Display, Formatter, LowerExp},
+    ops::Mul,
+};
+
+use super::{
+    format::{write_matrix, Notation},
+    matrix::is_negligible_determinant,
+    Matrix, Real, Scalar, Vector,
+};
+
+/// A 4x4 matrix for homogeneous transforms.
+///
+/// Vectors are treated as columns, so `a * b` applies `b` first. The camera
+/// constructors follow the OpenGL conventions: the view space is
+/// right-handed, the camera looks down -z and the clip space depth is
+/// [-1, 1].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Matrix4<T = f32> {
+    rows: [[T; 4]; 4],
+}
+
+impl<T: Scalar> Matrix4<T> {
+    pub fn from_rows(rows: [[T; 4]; 4]) -> Self {
+        Self { rows }
+    }
+
+    pub fn zero() -> Self {
+        Self::from_rows([[T::ZERO; 4]; 4])
+    }
+
+    pub fn identity() -> Self {
+        let mut mat = Self::zero();
+        for i in 0..4 {
+            mat.rows[i][i] = T::ONE;
+        }
+        mat
+    }
+
+    /// Embed a 3x3 linear transform into the upper-left corner.
+    pub fn from_matrix(mat: &Matrix<T>) -> Self {
+        let mut rows = Self::identity().rows;
+        for (i, row) in rows.iter_mut().enumerate().take(3) {
+            let mat_row = mat.row(i);
+            row[0] = mat_row.x;
+            row[1] = mat_row.y;
+            row[2] = mat_row.z;
+        }
+        Self::from_rows(rows)
+    }
+
+    pub fn translation(offset: &Vector<T>) -> Self {
+        let mut mat = Self::identity();
+        mat.rows[0][3] = offset.x;
+        mat.rows[1][3] = offset.y;
+        mat.rows[2][3] = offset.z;
+        mat
+    }
+
+    pub fn scale(factor: &Vector<T>) -> Self {
+        let mut mat = Self::identity();
+        mat.rows[0][0] = factor.x;
+        mat.rows[1][1] = factor.y;
+        mat.rows[2][2] = factor.z;
+        mat
+    }
+
+    /// Get the row at `index`, which should be in [0, 3].
+    pub fn row(&self, index: usize) -> [T; 4] {
+        self.rows[index]
+    }
+
+    /// Get the column at `index`, which should be in [0, 3].
+    pub fn column(&self, index: usize) -> [T; 4] {
+        [
+            self.rows[0][index],
+            self.rows[1][index],
+            self.rows[2][index],
+            self.rows[3][index],
+        ]
+    }
+
+    /// The upper-left 3x3 block, i.e. the linear part of an affine transform.
+    pub fn linear_part(&self) -> Matrix<T> {
+        let r = &self.rows;
+        Matrix::new(
+            r[0][0], r[0][1], r[0][2], r[1][0], r[1][1], r[1][2], r[2][0], r[2][1], r[2][2],
+        )
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut rows = self.rows;
+        for (i, row) in rows.iter_mut().enumerate() {
+            *row = self.column(i);
+        }
+        Self::from_rows(rows)
+    }
+
+    /// Sub-determinants of the 2x2 blocks in the top two rows and the bottom
+    /// two rows, shared by `determinant` and `inverse`.
+    fn sub_determinants(&self) -> ([T; 6], [T; 6]) {
+        let a = &self.rows;
+        let s = [
+            a[0][0] * a[1][1] - a[1][0] * a[0][1],
+            a[0][0] * a[1][2] - a[1][0] * a[0][2],
+            a[0][0] * a[1][3] - a[1][0] * a[0][3],
+            a[0][1] * a[1][2] - a[1][1] * a[0][2],
+            a[0][1] * a[1][3] - a[1][1] * a[0][3],
+            a[0][2] * a[1][3] - a[1][2] * a[0][3],
+        ];
+        let c = [
+            a[2][0] * a[3][1] - a[3][0] * a[2][1],
+            a[2][0] * a[3][2] - a[3][0] * a[2][2],
+            a[2][0] * a[3][3] - a[3][0] * a[2][3],
+            a[2][1] * a[3][2] - a[3][1] * a[2][2],
+            a[2][1] * a[3][3] - a[3][1] * a[2][3],
+            a[2][2] * a[3][3] - a[3][2] * a[2][3],
+        ];
+        (s, c)
+    }
+
+    pub fn determinant(&self) -> T {
+        let (s, c) = self.sub_determinants();
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+
+    /// Transform a point, i.e. a vector with `w = 1`. The result is divided
+    /// by its `w` component if that is not 1, so projections work as well.
+    pub fn transform_point(&self, point: &Vector<T>) -> Vector<T> {
+        let [x, y, z, w] = self.mul_homogeneous([point.x, point.y, point.z, T::ONE]);
+        if w == T::ONE {
+            Vector::new(x, y, z)
+        } else {
+            Vector::new(x / w, y / w, z / w)
+        }
+    }
+
+    /// Transform a direction, i.e. a vector with `w = 0`. Translations have
+    /// no effect on it.
+    pub fn transform_direction(&self, direction: &Vector<T>) -> Vector<T> {
+        let [x, y, z, _] = self.mul_homogeneous([direction.x, direction.y, direction.z, T::ZERO]);
+        Vector::new(x, y, z)
+    }
+
+    fn mul_homogeneous(&self, v: [T; 4]) -> [T; 4] {
+        self.rows
+            .map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2] + row[3] * v[3])
+    }
+}
+
+impl<T: Real> Matrix4<T> {
+    /// Get the inverse with the Laplace expansion over 2x2 blocks. Return
+    /// `None` if the matrix is singular up to rounding, as in
+    /// `Matrix::inverse`.
+    pub fn inverse(&self) -> Option<Self> {
+        let (s, c) = self.sub_determinants();
+        let det = s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+        if is_negligible_determinant(det, &self.rows) {
+            return None;
+        }
+        let inv_det = T::ONE / det;
+
+        let a = &self.rows;
+        let rows = [
+            [
+                a[1][1] * c[5] - a[1][2] * c[4] + a[1][3] * c[3],
+                -a[0][1] * c[5] + a[0][2] * c[4] - a[0][3] * c[3],
+                a[3][1] * s[5] - a[3][2] * s[4] + a[3][3] * s[3],
+                -a[2][1] * s[5] + a[2][2] * s[4] - a[2][3] * s[3],
+            ],
+            [
+                -a[1][0] * c[5] + a[1][2] * c[2] - a[1][3] * c[1],
+                a[0][0] * c[5] - a[0][2] * c[2] + a[0][3] * c[1],
+                -a[3][0] * s[5] + a[3][2] * s[2] - a[3][3] * s[1],
+                a[2][0] * s[5] - a[2][2] * s[2] + a[2][3] * s[1],
+            ],
+            [
+                a[1][0] * c[4] - a[1][1] * c[2] + a[1][3] * c[0],
+                -a[0][0] * c[4] + a[0][1] * c[2] - a[0][3] * c[0],
+                a[3][0] * s[4] - a[3][1] * s[2] + a[3][3] * s[0],
+                -a[2][0] * s[4] + a[2][1] * s[2] - a[2][3] * s[0],
+            ],
+            [
+                -a[1][0] * c[3] + a[1][1] * c[1] - a[1][2] * c[0],
+                a[0][0] * c[3] - a[0][1] * c[1] + a[0][2] * c[0],
+                -a[3][0] * s[3] + a[3][1] * s[1] - a[3][2] * s[0],
+                a[2][0] * s[3] - a[2][1] * s[1] + a[2][2] * s[0],
+            ],
+        ];
+
+        Some(Self::from_rows(
+            rows.map(|row| row.map(|each| each * inv_det)),
+        ))
+    }
+
+    /// Rotation of `angle` radians around `axis`, counter-clockwise when
+    /// looking against the axis. The axis does not need to be normalized.
+    pub fn rotation(axis: &Vector<T>, angle: T) -> Self {
+        let axis = axis / axis.length();
+        let (sin, cos) = (angle.sin(), angle.cos());
+        let one_minus_cos = T::ONE - cos;
+        let (x, y, z) = (axis.x, axis.y, axis.z);
+
+        Self::from_matrix(&Matrix::new(
+            cos + x * x * one_minus_cos,
+            x * y * one_minus_cos - z * sin,
+            x * z * one_minus_cos + y * sin,
+            y * x * one_minus_cos + z * sin,
+            cos + y * y * one_minus_cos,
+            y * z * one_minus_cos - x * sin,
+            z * x * one_minus_cos - y * sin,
+            z * y * one_minus_cos + x * sin,
+            cos + z * z * one_minus_cos,
+        ))
+    }
+
+    /// The view matrix of a camera at `eye` looking at `target`. `up` must
+    /// not be parallel to the viewing direction.
+    pub fn look_at(eye: &Vector<T>, target: &Vector<T>, up: &Vector<T>) -> Self {
+        let forward = target - eye;
+        let forward = forward / forward.length();
+        let side = forward.cross_product(up);
+        let side = side / side.length();
+        let up = side.cross_product(&forward);
+
+        Self::from_rows([
+            [side.x, side.y, side.z, -side.dot_product(eye)],
+            [up.x, up.y, up.z, -up.dot_product(eye)],
+            [-forward.x, -forward.y, -forward.z, forward.dot_product(eye)],
+            [T::ZERO, T::ZERO, T::ZERO, T::ONE],
+        ])
+    }
+
+    /// Map the box [left, right] x [bottom, top] x [-near, -far] onto the
+    /// clip space cube.
+    pub fn orthographic(left: T, right: T, bottom: T, top: T, near: T, far: T) -> Self {
+        let two = T::from_f64(2.0);
+        Self::from_rows([
+            [
+                two / (right - left),
+                T::ZERO,
+                T::ZERO,
+                -(right + left) / (right - left),
+            ],
+            [
+                T::ZERO,
+                two / (top - bottom),
+                T::ZERO,
+                -(top + bottom) / (top - bottom),
+            ],
+            [
+                T::ZERO,
+                T::ZERO,
+                -two / (far - near),
+                -(far + near) / (far - near),
+            ],
+            [T::ZERO, T::ZERO, T::ZERO, T::ONE],
+        ])
+    }
+
+    /// Perspective projection with a vertical field of view of `fov_y`
+    /// radians and `aspect = width / height`.
+    pub fn perspective(fov_y: T, aspect: T, near: T, far: T) -> Self {
+        let two = T::from_f64(2.0);
+        let focal = T::ONE / (fov_y / two).tan();
+        Self::from_rows([
+            [focal / aspect, T::ZERO, T::ZERO, T::ZERO],
+            [T::ZERO, focal, T::ZERO, T::ZERO],
+            [
+                T::ZERO,
+                T::ZERO,
+                (far + near) / (near - far),
+                two * far * near / (near - far),
+            ],
+            [T::ZERO, T::ZERO, -T::ONE, T::ZERO],
+        ])
+    }
+
+    /// Transform a surface normal with the inverse transpose of the linear
+    /// part. See `Matrix::transform_normal`.
+    pub fn transform_normal(&self, normal: &Vector<T>) -> Vector<T> {
+        self.linear_part().transform_normal(normal)
+    }
+}
+
+impl<T: Scalar> Mul<&Matrix4<T>> for &Matrix4<T> {
+    type Output = Matrix4<T>;
+
+    /// Matrix Multiplication
+    fn mul(self, rhs: &Matrix4<T>) -> Matrix4<T> {
+        let mut rows = [[T::ZERO; 4]; 4];
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, each) in row.iter_mut().enumerate() {
+                for k in 0..4 {
+                    *each += self.rows[i][k] * rhs.rows[k][j];
+                }
+            }
+        }
+        Matrix4::from_rows(rows)
+    }
+}
+
+impl<T: Scalar> Mul<Matrix4<T>> for &Matrix4<T> {
+    type Output = Matrix4<T>;
+
+    /// Matrix Multiplication
+    fn mul(self, rhs: Matrix4<T>) -> Matrix4<T> {
+        self * &rhs
+    }
+}
+
+impl<T: Scalar> Mul<Matrix4<T>> for Matrix4<T> {
+    type Output = Matrix4<T>;
+
+    /// Matrix Multiplication
+    fn mul(self, rhs: Matrix4<T>) -> Matrix4<T> {
+        &self * &rhs
+    }
+}
+
+impl<T: Scalar> Display for Matrix4<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_matrix(f, &self.rows, Notation::Decimal)
+    }
+}
+
+impl<T: Scalar> LowerExp for Matrix4<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_matrix(f, &self.rows, Notation::Exponent)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::Matrix4;
+    use crate::math::Vector;
+
+    fn assert_close(a: Vector<f64>, b: Vector<f64>) {
+        assert!((a - b).length() < 1e-12, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_transform_kinds() {
+        let mat = Matrix4::translation(&Vector::new(1.0, 2.0, 3.0))
+            * Matrix4::rotation(&Vector::new(0.0, 0.0, 2.0), FRAC_PI_2);
+        let x = Vector::new(1.0, 0.0, 0.0);
+
+        assert_close(mat.transform_point(&x), Vector::new(1.0, 3.0, 3.0));
+        assert_close(mat.transform_direction(&x), Vector::new(0.0, 1.0, 0.0));
+
+        // a plane with normal (1, 1, 0) squashed along x
+        let scale = Matrix4::<f64>::scale(&Vector::new(0.5, 1.0, 1.0));
+        let normal = scale.transform_normal(&Vector::new(1.0, 1.0, 0.0));
+        let tangent = scale.transform_direction(&Vector::new(1.0, -1.0, 0.0));
+        assert!(normal.dot_product(&tangent).abs() < 1e-12);
+        assert!((normal.length() - 1.0).abs() < 1e-12);
+
+        let mirror = Matrix4::scale(&Vector::new(-1.0, 1.0, 1.0));
+        assert_close(mirror.transform_normal(&x), Vector::new(-1.0, 0.0, 0.0));
+
+        // squashed into the xy plane, whose normal is z
+        let flat = Matrix4::<f64>::scale(&Vector::new(2.0, 1.0, 0.0));
+        let z = Vector::new(0.0, 0.0, 1.0);
+        assert_close(flat.transform_normal(&Vector::new(0.6, 0.0, 0.8)), z);
+        // squashed into the x axis, or a normal that is squashed away
+        let line = Matrix4::<f64>::scale(&Vector::new(1.0, 0.0, 0.0));
+        let zero = Vector::new(0.0, 0.0, 0.0);
+        assert_eq!(line.transform_normal(&z), zero);
+        assert_eq!(flat.transform_normal(&x), zero);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let mat = Matrix4::<f64>::perspective(1.0, 1.5, 0.1, 100.0)
+            * Matrix4::look_at(
+                &Vector::new(3.0, 4.0, 5.0),
+                &Vector::new(0.0, 0.0, 0.0),
+                &Vector::new(0.0, 1.0, 0.0),
+            );
+        let inv = mat.inverse().unwrap();
+        let product = &mat * &inv;
+        let identity = Matrix4::<f64>::identity();
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((product.row(i)[j] - identity.row(i)[j]).abs() < 1e-12);
+            }
+        }
+        assert!((mat.determinant() * inv.determinant() - 1.0).abs() < 1e-9);
+
+        assert!(Matrix4::scale(&Vector::new(1.0, 0.0, 1.0))
+            .inverse()
+            .is_none());
+        // squashed flat, but rounding leaves a tiny determinant
+        let flat = Matrix4::<f64>::from_rows([
+            [0.1, 0.2, 0.3, 0.0],
+            [0.4, 0.5, 0.6, 0.0],
+            [0.7, 0.8, 0.9, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        assert_ne!(flat.determinant(), 0.0);
+        assert!(flat.inverse().is_none());
+        // a far translation is still invertible
+        let far = Matrix4::<f64>::translation(&Vector::new(1e8, -1e8, 1e8));
+        assert!(far.inverse().is_some());
+    }
+
+    #[test]
+    fn test_camera() {
+        let eye = Vector::new(3.0, 4.0, 5.0);
+        let view = Matrix4::look_at(
+            &eye,
+            &Vector::new(3.0, 4.0, 0.0),
+            &Vector::new(0.0, 1.0, 0.0),
+        );
+        assert_close(view.transform_point(&eye), Vector::new(0.0, 0.0, 0.0));
+        assert_close(
+            view.transform_point(&Vector::new(3.0, 4.0, 0.0)),
+            Vector::new(0.0, 0.0, -5.0),
+        );
+
+        let ortho = Matrix4::orthographic(-2.0, 2.0, -1.0, 1.0, 1.0, 3.0);
+        assert_close(
+            ortho.transform_point(&Vector::new(2.0, -1.0, -1.0)),
+            Vector::new(1.0, -1.0, -1.0),
+        );
+        assert_close(
+            ortho.transform_point(&Vector::new(-2.0, 1.0, -3.0)),
+            Vector::new(-1.0, 1.0, 1.0),
+        );
+
+        let persp = Matrix4::perspective(FRAC_PI_2, 2.0, 1.0, 10.0);
+        assert_close(
+            persp.transform_point(&Vector::new(2.0, 1.0, -1.0)),
+            Vector::new(1.0, 1.0, -1.0),
+        );
+        assert_close(
+            persp.transform_point(&Vector::new(0.0, 0.0, -10.0)),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+    }
+}
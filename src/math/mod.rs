@@ -1,39 +1,261 @@
+mod approx;
+pub mod batch;
+mod decomposition;
+mod dynamic_matrix;
+mod fixed;
+mod format;
+mod geometry;
+mod interval;
 mod matrix;
-mod vector;
-pub mod utils;
+mod matrix4;
+mod onb;
+mod polynomial;
+mod predicates;
+mod quaternion;
 pub mod rand;
+mod rational;
+mod scalar;
+/// SIMD versions of the `f32` kernels, built on the portable `wide` crate,
+/// so no target feature is needed at compile time.
+#[cfg(feature = "simd")]
+pub mod simd;
+mod spherical;
+pub mod utils;
+mod vector;
+mod vector2;
+mod vector4;
 
+pub use approx::*;
+pub use decomposition::*;
+pub use dynamic_matrix::*;
+pub use fixed::*;
+pub use geometry::*;
+pub use interval::*;
 pub use matrix::*;
+pub use matrix4::*;
+pub use onb::*;
+pub use polynomial::*;
+pub use predicates::*;
+pub use quaternion::*;
+pub use rational::*;
+pub use scalar::*;
+pub use spherical::*;
 pub use vector::*;
+pub use vector2::*;
+pub use vector4::*;
 
-#[derive(Debug)]
-pub enum QuadraticResult {
-    Roots(f32, f32),
-    DoubleRoot(f32),
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum QuadraticResult<T = f32> {
+    /// Two distinct roots in ascending order.
+    Roots(T, T),
+    DoubleRoot(T),
+    /// Exactly one simple root: the root of a linear equation, i.e. `a = 0`,
+    /// or from `quadratic_equation_in_range` the only one of two distinct
+    /// roots in range.
+    SingleRoot(T),
     NoRealSolution,
+    /// Every coefficient is zero, so every number is a root.
+    AllNumbers,
 }
 
-pub fn quadratic_equation(a: f32, b: f32, c: f32) -> QuadraticResult {
-    let discriminant = b.powi(2) - 4.0 * a * c;
-    if discriminant < 0.0 {
+/// Solve `a x^2 + b x + c = 0`. If `a` is zero the linear equation
+/// `b x + c = 0` is solved instead.
+///
+/// The discriminant is compared with a tolerance relative to the terms it
+/// is computed from, so nearly tangent cases are reported as a double root
+/// instead of depending on the scale of the coefficients.
+pub fn quadratic_equation<T: Real>(a: T, b: T, c: T) -> QuadraticResult<T> {
+    if a == T::ZERO {
+        return if b != T::ZERO {
+            QuadraticResult::SingleRoot(-c / b)
+        } else if c == T::ZERO {
+            QuadraticResult::AllNumbers
+        } else {
+            QuadraticResult::NoRealSolution
+        };
+    }
+
+    let two = T::from_f64(2.0);
+    let four_ac = T::from_f64(4.0) * a * c;
+    let discriminant = b.powi(2) - four_ac;
+    let tolerance = T::from_f64(4.0) * T::EPSILON * b.powi(2).max(four_ac.abs());
+    if discriminant < -tolerance {
         return QuadraticResult::NoRealSolution;
     }
 
-    let discriminant_sqrt = discriminant.sqrt();
-    if discriminant - 0.0 < f32::EPSILON {
-        let double_root = -b / 2.0 / a;
+    if discriminant <= tolerance {
+        let double_root = -b / two / a;
         QuadraticResult::DoubleRoot(double_root)
     } else {
-        let [x1, x2] = if b >= 0.0 {
-            let x1 = 2.0 * c / (-b - discriminant_sqrt); // numerical stability
-            let x2 = (-b - discriminant_sqrt) / 2.0 / a;
+        let discriminant_sqrt = discriminant.sqrt();
+        let [x1, x2] = if b >= T::ZERO {
+            let x1 = two * c / (-b - discriminant_sqrt); // numerical stability
+            let x2 = (-b - discriminant_sqrt) / two / a;
             [x1, x2]
         } else {
-            let x1 = (-b + discriminant_sqrt) / 2.0 / a;
-            let x2 = 2.0 * c / (-b + discriminant_sqrt); // numerical stability
+            let x1 = (-b + discriminant_sqrt) / two / a;
+            let x2 = two * c / (-b + discriminant_sqrt); // numerical stability
             [x1, x2]
         };
 
-        QuadraticResult::Roots(x1, x2)
+        if x1 <= x2 {
+            QuadraticResult::Roots(x1, x2)
+        } else {
+            QuadraticResult::Roots(x2, x1)
+        }
+    }
+}
+
+/// Like `quadratic_equation`, but only keep the roots in
+/// `[t_min, t_max]`. If only one of two distinct roots is left, it is
+/// reported as `SingleRoot`. Since the roots are ascending, the first value
+/// of the result is always the nearest hit of a ray.
+pub fn quadratic_equation_in_range<T: Real>(
+    a: T,
+    b: T,
+    c: T,
+    t_min: T,
+    t_max: T,
+) -> QuadraticResult<T> {
+    let in_range = |t: T| t_min <= t && t <= t_max;
+    match quadratic_equation(a, b, c) {
+        QuadraticResult::Roots(x1, x2) => match (in_range(x1), in_range(x2)) {
+            (true, true) => QuadraticResult::Roots(x1, x2),
+            (true, false) => QuadraticResult::SingleRoot(x1),
+            (false, true) => QuadraticResult::SingleRoot(x2),
+            (false, false) => QuadraticResult::NoRealSolution,
+        },
+        QuadraticResult::DoubleRoot(x) if in_range(x) => QuadraticResult::DoubleRoot(x),
+        QuadraticResult::SingleRoot(x) if in_range(x) => QuadraticResult::SingleRoot(x),
+        QuadraticResult::AllNumbers if t_min <= t_max => QuadraticResult::AllNumbers,
+        _ => QuadraticResult::NoRealSolution,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{quadratic_equation, quadratic_equation_in_range, QuadraticResult};
+
+    #[test]
+    fn test_quadratic() {
+        // (x - 3)(x + 2) with both signs of b
+        assert_eq!(
+            quadratic_equation(1.0, -1.0, -6.0),
+            QuadraticResult::Roots(-2.0, 3.0)
+        );
+        assert_eq!(
+            quadratic_equation(-1.0, -1.0, 6.0),
+            QuadraticResult::Roots(-3.0, 2.0)
+        );
+        assert_eq!(
+            quadratic_equation(1.0, 2.0, 5.0),
+            QuadraticResult::NoRealSolution
+        );
+
+        // (1e4 x - 1)^2 has a tiny discriminant that only cancels relatively
+        assert_eq!(
+            quadratic_equation(1e8f32, -2e4, 1.0),
+            QuadraticResult::DoubleRoot(1e-4)
+        );
+        assert_eq!(
+            quadratic_equation(1e-8f32, 2e-8, 1e-8),
+            QuadraticResult::DoubleRoot(-1.0)
+        );
+
+        assert_eq!(
+            quadratic_equation(0.0, 2.0, -1.0),
+            QuadraticResult::SingleRoot(0.5)
+        );
+        assert_eq!(
+            quadratic_equation(0.0, 0.0, 1.0),
+            QuadraticResult::NoRealSolution
+        );
+        assert_eq!(
+            quadratic_equation(0.0, 0.0, 0.0),
+            QuadraticResult::AllNumbers
+        );
+    }
+
+    #[test]
+    fn test_quadratic_in_range() {
+        // a ray starting inside a unit sphere hits it at t = -1 and t = 1
+        assert_eq!(
+            quadratic_equation_in_range(1.0, 0.0, -1.0, 0.0, f32::INFINITY),
+            QuadraticResult::SingleRoot(1.0)
+        );
+        assert_eq!(
+            quadratic_equation_in_range(1.0, 0.0, -1.0, -1.0, 1.0),
+            QuadraticResult::Roots(-1.0, 1.0)
+        );
+        assert_eq!(
+            quadratic_equation_in_range(1.0, 0.0, -1.0, -0.5, 0.5),
+            QuadraticResult::NoRealSolution
+        );
+        assert_eq!(
+            quadratic_equation_in_range(1.0, -2.0, 1.0, 0.0, 2.0),
+            QuadraticResult::DoubleRoot(1.0)
+        );
+        assert_eq!(
+            quadratic_equation_in_range(1.0, -2.0, 1.0, 2.0, 3.0),
+            QuadraticResult::NoRealSolution
+        );
+        assert_eq!(
+            quadratic_equation_in_range(0.0, 1.0, 1.0, 0.0, 1.0),
+            QuadraticResult::NoRealSolution
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        use std::fmt::Debug;
+
+        use serde::{de::DeserializeOwned, Serialize};
+
+        use super::{
+            BasisMethod, Direction, DynamicMatrix, Interval, Matrix, Matrix4, Normal, Onb, Point,
+            Quaternion, Vector, Vector2i, Vector4,
+        };
+
+        fn round_trip<V: Serialize + DeserializeOwned + PartialEq + Debug>(value: V) {
+            let json = serde_json::to_string(&value).unwrap();
+            assert_eq!(serde_json::from_str::<V>(&json).unwrap(), value, "{}", json);
+            let binary = bincode::serialize(&value).unwrap();
+            assert_eq!(bincode::deserialize::<V>(&binary).unwrap(), value);
+        }
+
+        let v = Vector::new(1.5, -2.0, 0.1);
+        round_trip(v);
+        round_trip(Vector::<f64>::new(0.1, 1e-300, -7.0));
+        round_trip(Vector2i::new(640, -480));
+        round_trip(Vector4::new(0.25, 0.5, 0.75, 1.0));
+        round_trip(Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 0.1));
+        round_trip(Matrix4::look_at(
+            &v,
+            &Vector::new(0.0, 0.0, 0.0),
+            &Vector::new(0.0, 1.0, 0.0),
+        ));
+        round_trip(Quaternion::from_axis_angle(&v, 0.3));
+        round_trip((Point(v), Direction(v), Normal(v)));
+        round_trip(Onb::from_w(&v, BasisMethod::Revised).unwrap());
+        round_trip(quadratic_equation(1.0, -1.0, -6.0));
+        round_trip(QuadraticResult::<f32>::AllNumbers);
+        round_trip(DynamicMatrix::from_rows(&[
+            [1.0, 2.0, 3.0],
+            [4.0, 5.0, 0.1],
+        ]));
+
+        // checked like the constructors
+        let json = r#"{"rows":2,"columns":2,"data":[1.0,2.0,3.0]}"#;
+        assert!(serde_json::from_str::<DynamicMatrix>(json).is_err());
+        round_trip(Interval::new(-0.5, 0.1));
+        assert!(serde_json::from_str::<Interval>(r#"{"lo":2.0,"hi":1.0}"#).is_err());
+
+        // plain field names, so config files stay readable
+        assert_eq!(
+            serde_json::to_string(&Vector::new(1.0, 2.0, 3.0)).unwrap(),
+            r#"{"x":1.0,"y":2.0,"z":3.0}"#
+        );
     }
 }
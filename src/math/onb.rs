@@ -0,0 +1,124 @@
+use super::{Matrix, Real, Vector};
+
+/// The algorithm used to complete a basis from a single vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BasisMethod {
+    /// See `Vector::orthonormal_basis_simple`.
+    Simple,
+    /// See `Vector::orthonormal_basis_frisvad`.
+    Frisvad,
+    /// See `Vector::orthonormal_basis_revised`.
+    Revised,
+}
+
+/// An orthonormal basis. `(u, v, w)` is right-handed, i.e.
+/// `u.cross_product(&v) == w`, and `w` is usually the surface normal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Onb<T = f32> {
+    pub u: Vector<T>,
+    pub v: Vector<T>,
+    pub w: Vector<T>,
+}
+
+impl<T: Real> Onb<T> {
+    /// Build a basis around the direction of `w`. Return `None` if `w` is a
+    /// zero vector.
+    pub fn from_w(w: &Vector<T>, method: BasisMethod) -> Option<Self> {
+        let (u, v) = match method {
+            BasisMethod::Simple => w.orthonormal_basis_simple(),
+            BasisMethod::Frisvad => w.orthonormal_basis_frisvad(),
+            BasisMethod::Revised => w.orthonormal_basis_revised(),
+        }?;
+
+        Some(Self {
+            u,
+            v,
+            w: w / w.length(),
+        })
+    }
+
+    /// Build a basis around the direction of `w` with `u` as close to the
+    /// given `u` as possible, e.g. a normal and a tangent. Return `None` if
+    /// either is a zero vector or they are parallel.
+    pub fn from_wu(w: &Vector<T>, u: &Vector<T>) -> Option<Self> {
+        let w_len = w.length();
+        if w_len == T::ZERO {
+            return None;
+        }
+        let w = w / w_len;
+
+        // Gram-Schmidt
+        let u = u - w * w.dot_product(u);
+        let u_len = u.length();
+        if u_len == T::ZERO {
+            return None;
+        }
+        let u = u / u_len;
+
+        Some(Self {
+            u,
+            v: w.cross_product(&u),
+            w,
+        })
+    }
+
+    /// Express a world space vector in this basis.
+    pub fn to_local(&self, vector: &Vector<T>) -> Vector<T> {
+        Vector::new(
+            vector.dot_product(&self.u),
+            vector.dot_product(&self.v),
+            vector.dot_product(&self.w),
+        )
+    }
+
+    /// Convert a vector expressed in this basis back to world space.
+    pub fn to_world(&self, vector: &Vector<T>) -> Vector<T> {
+        self.u * vector.x + self.v * vector.y + self.w * vector.z
+    }
+
+    /// The matrix doing the same as `to_local`. Its transpose does the same
+    /// as `to_world`.
+    pub fn to_matrix(&self) -> Matrix<T> {
+        Matrix::from_vectors(self.u, self.v, self.w)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BasisMethod, Onb};
+    use crate::math::Vector;
+
+    fn assert_close(a: Vector<f64>, b: Vector<f64>) {
+        assert!((a - b).length() < 1e-12, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_onb() {
+        let n = Vector::new(0.2, -0.7, 0.4);
+        let d = Vector::new(3.0, 1.0, -2.0);
+        for method in [
+            BasisMethod::Simple,
+            BasisMethod::Frisvad,
+            BasisMethod::Revised,
+        ] {
+            let onb = Onb::from_w(&n, method).unwrap();
+            assert_close(onb.u.cross_product(&onb.v), onb.w);
+            assert_close(onb.to_local(&n), Vector::new(0.0, 0.0, n.length()));
+            assert_close(onb.to_world(&onb.to_local(&d)), d);
+            assert_close(&onb.to_matrix() * d, onb.to_local(&d));
+        }
+        assert!(Onb::from_w(&Vector::new(0.0, 0.0, 0.0), BasisMethod::Revised).is_none());
+    }
+
+    #[test]
+    fn test_onb_from_wu() {
+        let onb = Onb::from_wu(&Vector::new(0.0, 0.0, 2.0), &Vector::new(1.0, 0.0, 1.0)).unwrap();
+        assert_close(onb.u, Vector::new(1.0, 0.0, 0.0));
+        assert_close(onb.v, Vector::new(0.0, 1.0, 0.0));
+        assert_close(onb.w, Vector::new(0.0, 0.0, 1.0));
+
+        assert!(Onb::from_wu(&Vector::new(0.0, 0.0, 2.0), &Vector::new(0.0, 0.0, -1.0)).is_none());
+    }
+}
@@ -0,0 +1,535 @@
+use super::{quadratic_equation, QuadraticResult, Real};
+
+/// A real root of a polynomial and how many times it is repeated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Root<T = f32> {
+    pub value: T,
+    pub multiplicity: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PolynomialResult<T = f32> {
+    /// The distinct real roots in ascending order. Complex roots are left
+    /// out, so this is empty if there is no real root.
+    Roots(Vec<Root<T>>),
+    /// Every coefficient is zero, so every number is a root.
+    AllNumbers,
+}
+
+impl<T: Real> PolynomialResult<T> {
+    /// Sort the roots and merge the neighbours that are one multiple root of
+    /// the polynomial whose coefficients start from the highest degree.
+    fn from_values(mut values: Vec<(T, usize)>, coefficients: &[T]) -> Self {
+        // huge coefficients can overflow the formulas into infinity or NaN
+        values.retain(|(value, _)| value.is_finite());
+        values.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let critical_points = critical_points(coefficients);
+        let mut roots: Vec<Root<T>> = Vec::with_capacity(values.len());
+        for (value, multiplicity) in values {
+            let merged = roots.last().and_then(|last| {
+                let (lo, hi) = (last.value, value);
+                // by Rolle's theorem two distinct roots are separated by a
+                // critical point where the polynomial is not zero
+                let inside = || {
+                    critical_points
+                        .iter()
+                        .copied()
+                        .filter(|&x| lo < x && x < hi)
+                };
+                if inside().any(|x| !is_zero(coefficients, x)) {
+                    return None;
+                }
+                // the critical point is where a multiple root is best found
+                let total = T::from_f64((last.multiplicity + multiplicity) as f64);
+                let mean = (lo * T::from_f64(last.multiplicity as f64)
+                    + hi * T::from_f64(multiplicity as f64))
+                    / total;
+                Some(inside().next().unwrap_or(mean))
+            });
+            match (roots.last_mut(), merged) {
+                (Some(last), Some(merged)) => {
+                    last.value = merged;
+                    last.multiplicity += multiplicity;
+                }
+                _ => roots.push(Root {
+                    value,
+                    multiplicity,
+                }),
+            }
+        }
+
+        PolynomialResult::Roots(roots)
+    }
+
+    /// Refine the simple roots with a damped Newton's method on the original
+    /// polynomial, whose coefficients start from the highest degree.
+    fn polish(self, coefficients: &[T]) -> Self {
+        let PolynomialResult::Roots(mut roots) = self else {
+            return self;
+        };
+
+        for root in roots.iter_mut().filter(|root| root.multiplicity == 1) {
+            let mut x = root.value;
+            let (mut value, _) = evaluate(coefficients, x);
+            'newton: for _ in 0..8 {
+                let (_, derivative) = evaluate(coefficients, x);
+                let mut step = value / derivative;
+                // halve the step until it improves, as a full step jumps
+                // away between two close roots
+                while step.is_finite() {
+                    let next = x - step;
+                    if next == x {
+                        break 'newton;
+                    }
+                    let (next_value, _) = evaluate(coefficients, next);
+                    if next_value.abs() < value.abs() {
+                        x = next;
+                        value = next_value;
+                        continue 'newton;
+                    }
+                    step /= T::from_f64(2.0);
+                }
+                break;
+            }
+            root.value = x;
+        }
+        roots.sort_by(|a, b| a.value.partial_cmp(&b.value).unwrap());
+
+        PolynomialResult::Roots(roots)
+    }
+}
+
+/// The real roots of the derivative of a cubic or a quartic.
+fn critical_points<T: Real>(coefficients: &[T]) -> Vec<T> {
+    let degree = coefficients.len() - 1;
+    let derivative: Vec<T> = coefficients[..degree]
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| c * T::from_f64((degree - i) as f64))
+        .collect();
+    let result = match derivative[..] {
+        [a, b, c] => quadratic_equation(a, b, c).into(),
+        [a, b, c, d] => cubic_equation(a, b, c, d),
+        _ => PolynomialResult::Roots(vec![]),
+    };
+    match result {
+        PolynomialResult::Roots(roots) => roots.into_iter().map(|root| root.value).collect(),
+        PolynomialResult::AllNumbers => vec![],
+    }
+}
+
+/// Whether the polynomial is zero at `x` up to the rounding of its
+/// coefficients and of Horner's method.
+fn is_zero<T: Real>(coefficients: &[T], x: T) -> bool {
+    let (value, _) = evaluate(coefficients, x);
+    let magnitude = coefficients
+        .iter()
+        .fold(T::ZERO, |sum, &c| sum * x.abs() + c.abs());
+    let degree = T::from_f64((coefficients.len() - 1) as f64);
+    value.abs() <= degree * T::EPSILON * magnitude
+}
+
+/// Evaluate the polynomial and its derivative at `x` with Horner's method.
+fn evaluate<T: Real>(coefficients: &[T], x: T) -> (T, T) {
+    let mut value = T::ZERO;
+    let mut derivative = T::ZERO;
+    for &coefficient in coefficients {
+        derivative = derivative * x + value;
+        value = value * x + coefficient;
+    }
+    (value, derivative)
+}
+
+impl<T: Real> From<QuadraticResult<T>> for PolynomialResult<T> {
+    fn from(result: QuadraticResult<T>) -> Self {
+        let root = |value, multiplicity| Root {
+            value,
+            multiplicity,
+        };
+        match result {
+            QuadraticResult::Roots(x1, x2) => {
+                PolynomialResult::Roots(vec![root(x1, 1), root(x2, 1)])
+            }
+            QuadraticResult::DoubleRoot(x) => PolynomialResult::Roots(vec![root(x, 2)]),
+            QuadraticResult::SingleRoot(x) => PolynomialResult::Roots(vec![root(x, 1)]),
+            QuadraticResult::NoRealSolution => PolynomialResult::Roots(vec![]),
+            QuadraticResult::AllNumbers => PolynomialResult::AllNumbers,
+        }
+    }
+}
+
+/// Solve `a x^3 + b x^2 + c x + d = 0`. A zero `a` falls back to the
+/// quadratic case.
+///
+/// The depressed cubic is solved with the trigonometric method when there
+/// are three real roots and with Cardano's formula otherwise, then simple
+/// roots are polished with Newton's method. The multiplicity comes from the
+/// discriminant, and two roots are only reported as one multiple root if
+/// the polynomial is zero, up to rounding, at a critical point between them.
+pub fn cubic_equation<T: Real>(a: T, b: T, c: T, d: T) -> PolynomialResult<T> {
+    if a == T::ZERO {
+        return quadratic_equation(b, c, d).into();
+    }
+
+    let (a2, a1, a0) = (b / a, c / a, d / a);
+    let three = T::from_f64(3.0);
+    let two = T::from_f64(2.0);
+    let shift = -a2 / three;
+
+    // x = t + shift turns the equation into t^3 + p t + q = 0
+    let p = a1 - a2 * a2 / three;
+    let q = two * a2 * a2 * a2 / T::from_f64(27.0) - a2 * a1 / three + a0;
+
+    // rounding errors of `p` and `q`, from the largest term of each
+    let k = T::from_f64(8.0) * T::EPSILON;
+    let p_error = k * a1.abs().max(a2 * a2 / three);
+    let q_error = k
+        * (two * a2 * a2 * a2 / T::from_f64(27.0))
+            .abs()
+            .max((a2 * a1 / three).abs())
+            .max(a0.abs());
+
+    let coefficients = [T::ONE, a2, a1, a0];
+    let values = if p.abs() <= p_error && q.abs() <= q_error {
+        vec![(shift, 3)]
+    } else {
+        let half_q = q / two;
+        let third_p = p / three;
+        let discriminant = half_q * half_q + third_p * third_p * third_p;
+        let discriminant_error = half_q.abs() * q_error + third_p * third_p * p_error;
+
+        // t = -3q/(2p) would be the double root, so it is one only if the
+        // polynomial is zero there
+        let double = -three * q / (two * p) + shift;
+        if discriminant.abs() <= discriminant_error && is_zero(&coefficients, double) {
+            // t = 3q/p is the simple root
+            vec![(three * q / p + shift, 1), (double, 2)]
+        } else if discriminant > T::ZERO {
+            let u = -(half_q + discriminant.sqrt().copysign(half_q)).cbrt();
+            let t = if u == T::ZERO { u } else { u - third_p / u };
+            vec![(t + shift, 1)]
+        } else {
+            let r = (-third_p).sqrt();
+            let cos_phi = (-half_q / (r * r * r)).max(-T::ONE).min(T::ONE);
+            let phi = cos_phi.acos();
+            let two_pi = T::from_f64(2.0 * std::f64::consts::PI);
+            (0..3)
+                .map(|k| {
+                    let angle = (phi + two_pi * T::from_f64(k as f64)) / three;
+                    (two * r * angle.cos() + shift, 1)
+                })
+                .collect()
+        }
+    };
+
+    PolynomialResult::from_values(values, &coefficients).polish(&coefficients)
+}
+
+/// Solve `a x^4 + b x^3 + c x^2 + d x + e = 0`. A zero `a` falls back to the
+/// cubic case.
+///
+/// The depressed quartic is split into two quadratics with Ferrari's method,
+/// or solved as a quadratic in `x^2` when it is biquadratic. Simple roots
+/// are polished and multiple roots are merged as in `cubic_equation`.
+pub fn quartic_equation<T: Real>(a: T, b: T, c: T, d: T, e: T) -> PolynomialResult<T> {
+    if a == T::ZERO {
+        return cubic_equation(b, c, d, e);
+    }
+
+    let (a3, a2, a1, a0) = (b / a, c / a, d / a, e / a);
+    let two = T::from_f64(2.0);
+    let four = T::from_f64(4.0);
+    let eight = T::from_f64(8.0);
+    let shift = -a3 / four;
+
+    // x = y + shift turns the equation into y^4 + p y^2 + q y + r = 0
+    let a3_2 = a3 * a3;
+    let p = a2 - T::from_f64(3.0) * a3_2 / eight;
+    let q = a1 - a3 * a2 / two + a3_2 * a3 / eight;
+    let r = a0 - a3 * a1 / four + a3_2 * a2 / T::from_f64(16.0)
+        - T::from_f64(3.0) * a3_2 * a3_2 / T::from_f64(256.0);
+
+    let q_error = eight
+        * T::EPSILON
+        * a1.abs()
+            .max((a3 * a2 / two).abs())
+            .max((a3_2 * a3 / eight).abs());
+
+    let mut values = Vec::with_capacity(4);
+    let mut biquadratic = q.abs() <= q_error;
+    if !biquadratic {
+        // Ferrari: pick m so that (y^2 + p/2 + m)^2 = 2m (y - q/(4m))^2
+        let resolvent = cubic_equation(T::ONE, p, p * p / four - r, -q * q / eight);
+        let m = match resolvent {
+            PolynomialResult::Roots(roots) => roots.last().map(|root| root.value),
+            PolynomialResult::AllNumbers => None,
+        };
+
+        match m {
+            Some(m) if m > T::ZERO => {
+                let s = (two * m).sqrt();
+                let t = q / (two * s);
+                for (b, c) in [(-s, p / two + m + t), (s, p / two + m - t)] {
+                    if let PolynomialResult::Roots(roots) = quadratic_equation(T::ONE, b, c).into()
+                    {
+                        values.extend(
+                            roots
+                                .into_iter()
+                                .map(|root| (root.value + shift, root.multiplicity)),
+                        );
+                    }
+                }
+            }
+            _ => biquadratic = true,
+        }
+    }
+
+    if biquadratic {
+        // solve z^2 + p z + r = 0 with z = y^2
+        if let PolynomialResult::Roots(roots) = quadratic_equation(T::ONE, p, r).into() {
+            for root in roots {
+                if root.value > T::ZERO {
+                    let y = root.value.sqrt();
+                    values.push((-y + shift, root.multiplicity));
+                    values.push((y + shift, root.multiplicity));
+                } else if root.value == T::ZERO {
+                    values.push((shift, 2 * root.multiplicity));
+                }
+            }
+        }
+    }
+
+    let coefficients = [T::ONE, a3, a2, a1, a0];
+    PolynomialResult::from_values(values, &coefficients).polish(&coefficients)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{cubic_equation, quartic_equation, PolynomialResult, Root};
+
+    /// Expand `(x - r1)(x - r2)...` into coefficients from the highest degree.
+    fn expand(roots: &[f64]) -> Vec<f64> {
+        let mut coefficients = vec![1.0];
+        for &root in roots {
+            let mut next = coefficients.clone();
+            next.push(0.0);
+            for (i, &c) in coefficients.iter().enumerate() {
+                next[i + 1] -= root * c;
+            }
+            coefficients = next;
+        }
+        coefficients
+    }
+
+    fn assert_roots(result: PolynomialResult<f64>, expected: &[(f64, usize)], tolerance: f64) {
+        let PolynomialResult::Roots(roots) = result else {
+            panic!("expected roots, got {:?}", result);
+        };
+        assert_eq!(roots.len(), expected.len(), "{:?} != {:?}", roots, expected);
+        for (
+            Root {
+                value,
+                multiplicity,
+            },
+            &(expected_value, expected_multiplicity),
+        ) in roots.iter().zip(expected)
+        {
+            assert!(
+                (value - expected_value).abs() <= tolerance,
+                "{:?} != {:?}",
+                roots,
+                expected
+            );
+            assert_eq!(*multiplicity, expected_multiplicity);
+        }
+    }
+
+    fn cubic(roots: &[f64]) -> PolynomialResult<f64> {
+        let c = expand(roots);
+        cubic_equation(c[0], c[1], c[2], c[3])
+    }
+
+    fn quartic(roots: &[f64]) -> PolynomialResult<f64> {
+        let c = expand(roots);
+        quartic_equation(c[0], c[1], c[2], c[3], c[4])
+    }
+
+    #[test]
+    fn test_cubic() {
+        assert_roots(
+            cubic(&[3.0, 1.0, 2.0]),
+            &[(1.0, 1), (2.0, 1), (3.0, 1)],
+            1e-12,
+        );
+        assert_roots(cubic(&[1.0, 2.0, 1.0]), &[(1.0, 2), (2.0, 1)], 1e-9);
+        assert_roots(cubic(&[1.5, 1.5, 1.5]), &[(1.5, 3)], 1e-9);
+        assert_roots(
+            cubic(&[-1e3, 0.0, 1e-3]),
+            &[(-1e3, 1), (0.0, 1), (1e-3, 1)],
+            1e-12,
+        );
+
+        // (x + 2)(x^2 + 1)
+        assert_roots(cubic_equation(1.0, 2.0, 1.0, 2.0), &[(-2.0, 1)], 1e-12);
+        // scaled coefficients and a leading zero
+        assert_roots(
+            cubic_equation(-4.0, 0.0, 4.0, 0.0),
+            &[(-1.0, 1), (0.0, 1), (1.0, 1)],
+            1e-12,
+        );
+        assert_roots(cubic_equation(0.0, 1.0, -2.0, 1.0), &[(1.0, 2)], 1e-12);
+        assert_eq!(
+            cubic_equation(0.0, 0.0, 0.0, 0.0),
+            PolynomialResult::AllNumbers
+        );
+    }
+
+    #[test]
+    fn test_cubic_near_degenerate() {
+        // close but distinct roots must not be merged
+        assert_roots(
+            cubic(&[1.0, 1.0 + 1e-3, -5.0]),
+            &[(-5.0, 1), (1.0, 1), (1.0 + 1e-3, 1)],
+            1e-9,
+        );
+        // a perturbed double root is still reported as double
+        let c = expand(&[0.1, 0.1, 7.0]);
+        assert_roots(
+            cubic_equation(c[0], c[1] * (1.0 + 1e-15), c[2], c[3]),
+            &[(0.1, 2), (7.0, 1)],
+            1e-6,
+        );
+
+        let c = expand(&[0.5, 0.5, 0.5])
+            .iter()
+            .map(|&c| c as f32)
+            .collect::<Vec<_>>();
+        let PolynomialResult::Roots(roots) = cubic_equation(c[0], c[1], c[2], c[3]) else {
+            unreachable!()
+        };
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].multiplicity, 3);
+        assert!((roots[0].value - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_quartic() {
+        assert_roots(
+            quartic(&[4.0, 2.0, 3.0, 1.0]),
+            &[(1.0, 1), (2.0, 1), (3.0, 1), (4.0, 1)],
+            1e-12,
+        );
+        assert_roots(
+            quartic(&[1.0, 1.0, 2.0, 3.0]),
+            &[(1.0, 2), (2.0, 1), (3.0, 1)],
+            1e-9,
+        );
+        assert_roots(quartic(&[1.0, 1.0, 2.0, 2.0]), &[(1.0, 2), (2.0, 2)], 1e-9);
+        assert_roots(
+            quartic(&[1.0, 1.0, 1.0, -1.0]),
+            &[(-1.0, 1), (1.0, 3)],
+            1e-6,
+        );
+        assert_roots(quartic(&[2.0, 2.0, 2.0, 2.0]), &[(2.0, 4)], 1e-6);
+        assert_roots(
+            quartic(&[-1.0, 1.0, -1.0, 1.0]),
+            &[(-1.0, 2), (1.0, 2)],
+            1e-9,
+        );
+
+        // (x^2 + 1)(x^2 + 4)
+        assert_roots(quartic_equation(1.0, 0.0, 5.0, 0.0, 4.0), &[], 0.0);
+        // (x - 1)(x + 1)(x^2 + 1)
+        assert_roots(
+            quartic_equation(1.0, 0.0, 0.0, 0.0, -1.0),
+            &[(-1.0, 1), (1.0, 1)],
+            1e-12,
+        );
+        // (x - 3)(x^3 + x + 1), one real root of the cubic factor
+        assert_roots(
+            quartic_equation(1.0, -3.0, 1.0, -2.0, -3.0),
+            &[(-0.6823278038280193, 1), (3.0, 1)],
+            1e-12,
+        );
+        assert_roots(
+            quartic_equation(0.0, 1.0, -6.0, 11.0, -6.0),
+            &[(1.0, 1), (2.0, 1), (3.0, 1)],
+            1e-12,
+        );
+    }
+
+    #[test]
+    fn test_quartic_near_degenerate() {
+        // torus-like spread of roots
+        assert_roots(
+            quartic(&[-10.0, -9.999, 9.999, 10.0]),
+            &[(-10.0, 1), (-9.999, 1), (9.999, 1), (10.0, 1)],
+            1e-9,
+        );
+        assert_roots(
+            quartic(&[1e-2, 2e-2, 3e2, 4e2]),
+            &[(1e-2, 1), (2e-2, 1), (3e2, 1), (4e2, 1)],
+            1e-9,
+        );
+    }
+
+    #[test]
+    fn test_f32_near_degenerate() {
+        let roots = |result: PolynomialResult<f32>| match result {
+            PolynomialResult::Roots(roots) => roots
+                .iter()
+                .map(|root| (root.value, root.multiplicity))
+                .collect::<Vec<_>>(),
+            PolynomialResult::AllNumbers => unreachable!(),
+        };
+        let cubic = |r: &[f64]| {
+            let c: Vec<f32> = expand(r).iter().map(|&c| c as f32).collect();
+            roots(cubic_equation(c[0], c[1], c[2], c[3]))
+        };
+        let quartic = |r: &[f64]| {
+            let c: Vec<f32> = expand(r).iter().map(|&c| c as f32).collect();
+            roots(quartic_equation(c[0], c[1], c[2], c[3], c[4]))
+        };
+        let assert_close = |roots: Vec<(f32, usize)>, expected: &[(f32, usize)]| {
+            assert_eq!(roots.len(), expected.len(), "{:?} != {:?}", roots, expected);
+            for (&(value, multiplicity), &(expected_value, expected_multiplicity)) in
+                roots.iter().zip(expected)
+            {
+                assert!(
+                    (value - expected_value).abs() < 1e-3,
+                    "{:?} != {:?}",
+                    roots,
+                    expected
+                );
+                assert_eq!(multiplicity, expected_multiplicity);
+            }
+        };
+
+        // close but distinct
+        assert_close(cubic(&[1.0, 1.004, 5.0]), &[(1.0, 1), (1.004, 1), (5.0, 1)]);
+        assert_close(
+            quartic(&[1.0, 1.004, 5.0, -2.0]),
+            &[(-2.0, 1), (1.0, 1), (1.004, 1), (5.0, 1)],
+        );
+        // multiple
+        assert_close(cubic(&[0.3, 0.3, 2.0]), &[(0.3, 2), (2.0, 1)]);
+        assert_close(
+            quartic(&[1.0, 1.0, 2.0, 3.0]),
+            &[(1.0, 2), (2.0, 1), (3.0, 1)],
+        );
+        assert_close(quartic(&[0.5, 0.5, -1.5, -1.5]), &[(-1.5, 2), (0.5, 2)]);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let finite = |result: PolynomialResult<f64>| match result {
+            PolynomialResult::Roots(roots) => roots.iter().all(|root| root.value.is_finite()),
+            PolynomialResult::AllNumbers => false,
+        };
+        assert!(finite(cubic_equation(1e-300, 1e300, 1e300, -1e300)));
+        assert!(finite(quartic_equation(1e-300, 1e300, 1e300, 1.0, -1e300)));
+    }
+}
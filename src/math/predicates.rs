@@ -0,0 +1,329 @@
+use std::{
+    cmp::Ordering,
+    ops::{Add, Mul, Sub},
+};
+
+use super::{Real, Scalar, Vector, Vector2};
+
+/// Twice the signed area of the triangle `a b c`: positive if it turns
+/// counter-clockwise, negative if clockwise and zero if the points are
+/// collinear.
+///
+/// Like `orient3d` and `incircle`, it only multiplies, adds and subtracts,
+/// so the sign is exact whenever the scalar type is: integers, or `Rational`
+/// as long as its `i128` parts do not overflow, which only holds for small
+/// integer or dyadic inputs. With `f32` and `f64` nearly degenerate inputs
+/// can get the wrong sign; the `_exact` versions compute the sign exactly
+/// for any float coordinates.
+pub fn orient2d<T: Scalar>(a: &Vector2<T>, b: &Vector2<T>, c: &Vector2<T>) -> T {
+    (b - a).perp_dot_product(&(c - a))
+}
+
+/// Six times the signed volume of the tetrahedron `a b c d`: positive if `d`
+/// is on the side of the plane `a b c` from which the triangle turns
+/// counter-clockwise, i.e. along `(b - a) × (c - a)`, and zero if the points
+/// are coplanar.
+///
+/// Shewchuk's `orient3d` has the opposite sign.
+pub fn orient3d<T: Scalar>(a: &Vector<T>, b: &Vector<T>, c: &Vector<T>, d: &Vector<T>) -> T {
+    (b - a).cross_product(&(c - a)).dot_product(&(d - a))
+}
+
+/// Positive if `d` is inside the circle through `a b c`, negative if outside
+/// and zero if on it, provided `a b c` turns counter-clockwise. The sign is
+/// flipped for a clockwise triangle.
+pub fn incircle<T: Scalar>(a: &Vector2<T>, b: &Vector2<T>, c: &Vector2<T>, d: &Vector2<T>) -> T {
+    // lift onto the paraboloid z = x^2 + y^2, where the circle becomes a
+    // plane, and take the orientation against it
+    let lift = |p: &Vector2<T>| {
+        let p = p - d;
+        p.extend(p.dot_product(&p))
+    };
+    lift(a).cross_product(&lift(b)).dot_product(&lift(c))
+}
+
+/// `orient2d` of float coordinates, with the sign computed exactly.
+pub fn orient2d_exact<T: Real>(a: &Vector2<T>, b: &Vector2<T>, c: &Vector2<T>) -> Ordering {
+    let [u, v] = [b, c].map(|p| difference2(p, a));
+    (&(&u[0] * &v[1]) - &(&u[1] * &v[0])).sign()
+}
+
+/// `orient3d` of float coordinates, with the sign computed exactly.
+pub fn orient3d_exact<T: Real>(
+    a: &Vector<T>,
+    b: &Vector<T>,
+    c: &Vector<T>,
+    d: &Vector<T>,
+) -> Ordering {
+    let [u, v, w] = [b, c, d].map(|p| {
+        [
+            Expansion::difference(p.x.to_f64(), a.x.to_f64()),
+            Expansion::difference(p.y.to_f64(), a.y.to_f64()),
+            Expansion::difference(p.z.to_f64(), a.z.to_f64()),
+        ]
+    });
+    dot(&cross(&u, &v), &w).sign()
+}
+
+/// `incircle` of float coordinates, with the sign computed exactly.
+pub fn incircle_exact<T: Real>(
+    a: &Vector2<T>,
+    b: &Vector2<T>,
+    c: &Vector2<T>,
+    d: &Vector2<T>,
+) -> Ordering {
+    let [a, b, c] = [a, b, c].map(|p| {
+        let [x, y] = difference2(p, d);
+        let lifted = &(&x * &x) + &(&y * &y);
+        [x, y, lifted]
+    });
+    dot(&cross(&a, &b), &c).sign()
+}
+
+fn difference2<T: Real>(p: &Vector2<T>, q: &Vector2<T>) -> [Expansion; 2] {
+    [
+        Expansion::difference(p.x.to_f64(), q.x.to_f64()),
+        Expansion::difference(p.y.to_f64(), q.y.to_f64()),
+    ]
+}
+
+fn cross(u: &[Expansion; 3], v: &[Expansion; 3]) -> [Expansion; 3] {
+    [
+        &(&u[1] * &v[2]) - &(&u[2] * &v[1]),
+        &(&u[2] * &v[0]) - &(&u[0] * &v[2]),
+        &(&u[0] * &v[1]) - &(&u[1] * &v[0]),
+    ]
+}
+
+fn dot(u: &[Expansion; 3], v: &[Expansion; 3]) -> Expansion {
+    &(&(&u[0] * &v[0]) + &(&u[1] * &v[1])) + &(&u[2] * &v[2])
+}
+
+/// A number stored exactly as the sum of `f64` components that do not
+/// overlap, by increasing magnitude, so the largest one has the sign of the
+/// sum. Exact as long as no product overflows or underflows.
+///
+/// Shewchuk, "Adaptive Precision Floating-Point Arithmetic and Fast Robust
+/// Geometric Predicates", 1997.
+#[derive(Debug, Clone, Default)]
+struct Expansion(Vec<f64>);
+
+/// `a + b` as the rounded sum and its rounding error.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    let b_virtual = sum - a;
+    let a_virtual = sum - b_virtual;
+    (sum, (a - a_virtual) + (b - b_virtual))
+}
+
+/// `a * b` as the rounded product and its rounding error.
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let product = a * b;
+    (product, a.mul_add(b, -product))
+}
+
+impl Expansion {
+    fn difference(a: f64, b: f64) -> Self {
+        let (difference, error) = two_sum(a, -b);
+        Self::default().grow(error).grow(difference)
+    }
+
+    /// Add a single `f64`, dropping the zero components.
+    fn grow(&self, b: f64) -> Self {
+        let mut components = Vec::with_capacity(self.0.len() + 1);
+        let mut q = b;
+        for &e in &self.0 {
+            let (sum, error) = two_sum(q, e);
+            if error != 0.0 {
+                components.push(error);
+            }
+            q = sum;
+        }
+        if q != 0.0 {
+            components.push(q);
+        }
+        Self(components)
+    }
+
+    fn sign(&self) -> Ordering {
+        self.0
+            .last()
+            .map_or(Ordering::Equal, |largest| largest.total_cmp(&0.0))
+    }
+}
+
+impl Add for &Expansion {
+    type Output = Expansion;
+
+    fn add(self, rhs: Self) -> Expansion {
+        rhs.0.iter().fold(self.clone(), |sum, &b| sum.grow(b))
+    }
+}
+
+impl Sub for &Expansion {
+    type Output = Expansion;
+
+    fn sub(self, rhs: Self) -> Expansion {
+        rhs.0.iter().fold(self.clone(), |sum, &b| sum.grow(-b))
+    }
+}
+
+impl Mul for &Expansion {
+    type Output = Expansion;
+
+    fn mul(self, rhs: Self) -> Expansion {
+        let mut product = Expansion::default();
+        for &a in &self.0 {
+            for &b in &rhs.0 {
+                let (rounded, error) = two_product(a, b);
+                product = product.grow(error).grow(rounded);
+            }
+        }
+        product
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::{incircle, incircle_exact, orient2d, orient2d_exact, orient3d, orient3d_exact};
+    use crate::math::{Rational, Scalar, Vector, Vector2};
+
+    #[test]
+    fn test_orient() {
+        let a = Vector2::new(0i64, 0);
+        let b = Vector2::new(4, 0);
+        assert_eq!(orient2d(&a, &b, &Vector2::new(1, 3)), 12);
+        assert_eq!(orient2d(&a, &b, &Vector2::new(1, -3)), -12);
+        assert_eq!(orient2d(&a, &b, &Vector2::new(-7, 0)), 0);
+
+        let a = Vector::new(0i64, 0, 0);
+        let b = Vector::new(1, 0, 0);
+        let c = Vector::new(0, 1, 0);
+        assert_eq!(orient3d(&a, &b, &c, &Vector::new(0, 0, 1)), 1);
+        assert_eq!(orient3d(&a, &b, &c, &Vector::new(5, 5, -2)), -2);
+        assert_eq!(orient3d(&a, &b, &c, &Vector::new(5, -3, 0)), 0);
+    }
+
+    #[test]
+    fn test_orient_near_degenerate() {
+        // a = (0.5 + i ulp, 0.5 + j ulp), and the exact determinant is
+        // 12 (j - i) ulp, which plain f64 gets wrong for some of them
+        let b = Vector2::new(12.0, 12.0);
+        let c = Vector2::new(24.0, 24.0);
+        let mut wrong = 0;
+        for i in 0..32 {
+            for j in 0..32 {
+                let a = Vector2::new(0.5 + i as f64 * f64::EPSILON, 0.5 + j as f64 * f64::EPSILON);
+
+                let float = orient2d(&a, &b, &c);
+                if float.partial_cmp(&0.0) != Some(j.cmp(&i)) {
+                    wrong += 1;
+                }
+
+                let exact = orient2d(&a.cast::<Rational>(), &b.cast(), &c.cast());
+                let expected = Rational::from(12 * (j - i)) * Rational::from_f64(f64::EPSILON);
+                assert_eq!(exact, expected);
+                assert_eq!(orient2d_exact(&a, &b, &c), j.cmp(&i));
+            }
+        }
+        assert!(wrong > 0);
+    }
+
+    #[test]
+    fn test_incircle() {
+        let a = Vector2::new(1i64, 0);
+        let b = Vector2::new(0, 1);
+        let c = Vector2::new(-1, 0);
+        assert!(incircle(&a, &b, &c, &Vector2::new(0, 0)) > 0);
+        assert!(incircle(&a, &b, &c, &Vector2::new(2, 0)) < 0);
+        assert_eq!(incircle(&a, &b, &c, &Vector2::new(0, -1)), 0);
+        // clockwise
+        assert!(incircle(&c, &b, &a, &Vector2::new(0, 0)) < 0);
+
+        let third = Rational::new(1, 3);
+        let d = Vector2::new(third, Rational::from(-1) + third);
+        let [a, b, c] = [a, b, c].map(|p| p.cast::<Rational>());
+        assert!(incircle(&a, &b, &c, &d) > Rational::ZERO);
+    }
+
+    #[test]
+    fn test_exact_predicates() {
+        // non-dyadic coordinates, where `Rational` overflows i128
+        let v = |x: f64, y: f64, z: f64| Vector::new(x, y, z);
+        let (a, b, c, d) = (
+            v(0.1, 0.2, 0.3),
+            v(0.7, 0.1, 0.9),
+            v(0.3, 0.6, 0.2),
+            v(0.5, 0.5, 0.5),
+        );
+        let expected = orient3d(&a, &b, &c, &d).total_cmp(&0.0);
+        assert_eq!(orient3d_exact(&a, &b, &c, &d), expected);
+        assert_eq!(orient3d_exact(&a, &c, &b, &d), expected.reverse());
+        let [a, b, c, d] = [a, b, c, d].map(|p| Vector2::new(p.x, p.y));
+        let expected = incircle(&a, &b, &c, &d).total_cmp(&0.0);
+        assert_eq!(incircle_exact(&a, &b, &c, &d), expected);
+
+        // the orient2d case of `test_orient_near_degenerate` lifted to 3D:
+        // the exact volume is 12 (j - i) ulp
+        let b = v(12.0, 12.0, 0.0);
+        let c = v(24.0, 24.0, 0.0);
+        let d = v(0.0, 0.0, 1.0);
+        for i in 0..16 {
+            for j in 0..16 {
+                let a = v(
+                    0.5 + i as f64 * f64::EPSILON,
+                    0.5 + j as f64 * f64::EPSILON,
+                    0.0,
+                );
+                assert_eq!(orient3d_exact(&a, &b, &c, &d), j.cmp(&i));
+            }
+        }
+
+        // (x, y), (-x, y), (-x, -y) and (x, -y) are exactly on one circle
+        // around the origin, even if x^2 + y^2 is not a float
+        let (x, y) = (0.1f64, 0.7f64);
+        let a = Vector2::new(x, y);
+        let b = Vector2::new(-x, y);
+        let c = Vector2::new(-x, -y);
+        assert_eq!(
+            incircle_exact(&a, &b, &c, &Vector2::new(x, -y)),
+            Ordering::Equal
+        );
+        let outside = Vector2::new(x.next_up(), -y);
+        let inside = Vector2::new(x, (-y).next_up());
+        assert_eq!(incircle_exact(&a, &b, &c, &outside), Ordering::Less);
+        assert_eq!(incircle_exact(&a, &b, &c, &inside), Ordering::Greater);
+        assert_eq!(incircle_exact(&c, &b, &a, &inside), Ordering::Less);
+        let f32_point = Vector2::new(0.1f32, 0.7f32);
+        assert_eq!(
+            incircle_exact(&f32_point, &-f32_point.yx(), &-f32_point, &f32_point.yx()),
+            Ordering::Equal
+        );
+
+        // the same sign as `Rational` where it does not overflow
+        let mut rng = StdRng::seed_from_u64(19);
+        let mut point = || {
+            Vector2::new(
+                rng.gen_range(-8..=8) as f64 / 4.0,
+                rng.gen_range(-8..=8) as f64 / 4.0,
+            )
+        };
+        for _ in 0..2000 {
+            let [a, b, c, d] = [point(), point(), point(), point()];
+            let rational = [a, b, c, d].map(|p| p.cast::<Rational>());
+            let [ra, rb, rc, rd] = &rational;
+            assert_eq!(
+                orient2d_exact(&a, &b, &c),
+                orient2d(ra, rb, rc).cmp(&Rational::ZERO)
+            );
+            assert_eq!(
+                incircle_exact(&a, &b, &c, &d),
+                incircle(ra, rb, rc, rd).cmp(&Rational::ZERO)
+            );
+        }
+    }
+}
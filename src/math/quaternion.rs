@@ -0,0 +1,314 @@
+use std::ops::{Add, Mul};
+
+use super::{Matrix, Matrix4, Real, Scalar, Vector};
+
+/// A quaternion `w + v.x i + v.y j + v.z k`. Unit quaternions represent
+/// rotations, and `a * b` applies `b` first, matching the matrix types.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quaternion<T = f32> {
+    pub w: T,
+    pub v: Vector<T>,
+}
+
+impl<T: Scalar> Quaternion<T> {
+    pub fn new(w: T, x: T, y: T, z: T) -> Self {
+        Self::from_parts(w, Vector::new(x, y, z))
+    }
+
+    pub fn from_parts(w: T, v: Vector<T>) -> Self {
+        Self { w, v }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(T::ONE, T::ZERO, T::ZERO, T::ZERO)
+    }
+
+    pub fn conjugate(&self) -> Self {
+        Self::from_parts(self.w, -self.v)
+    }
+
+    pub fn dot_product(&self, other: &Self) -> T {
+        self.w * other.w + self.v.dot_product(&other.v)
+    }
+
+    pub fn norm_squared(&self) -> T {
+        self.dot_product(self)
+    }
+
+    /// Return `None` for the zero quaternion.
+    pub fn inverse(&self) -> Option<Self> {
+        let norm_squared = self.norm_squared();
+        if norm_squared == T::ZERO {
+            return None;
+        }
+
+        Some(self.conjugate() * (T::ONE / norm_squared))
+    }
+
+    /// Rotate `vector` by this quaternion, which should be a unit
+    /// quaternion.
+    pub fn rotate(&self, vector: &Vector<T>) -> Vector<T> {
+        // v' = v + 2w(q x v) + 2q x (q x v), i.e. q v q* expanded
+        let two = T::from_f64(2.0);
+        let t = self.v.cross_product(vector) * two;
+        vector + t * self.w + self.v.cross_product(&t)
+    }
+
+    /// The rotation matrix of this quaternion, which should be a unit
+    /// quaternion.
+    pub fn to_matrix(&self) -> Matrix<T> {
+        let two = T::from_f64(2.0);
+        let (w, x, y, z) = (self.w, self.v.x, self.v.y, self.v.z);
+
+        Matrix::new(
+            T::ONE - two * (y * y + z * z),
+            two * (x * y - w * z),
+            two * (x * z + w * y),
+            two * (x * y + w * z),
+            T::ONE - two * (x * x + z * z),
+            two * (y * z - w * x),
+            two * (x * z - w * y),
+            two * (y * z + w * x),
+            T::ONE - two * (x * x + y * y),
+        )
+    }
+
+    pub fn to_matrix4(&self) -> Matrix4<T> {
+        Matrix4::from_matrix(&self.to_matrix())
+    }
+}
+
+impl<T: Real> Quaternion<T> {
+    /// Rotation of `angle` radians around `axis`. The axis does not need to
+    /// be normalized.
+    pub fn from_axis_angle(axis: &Vector<T>, angle: T) -> Self {
+        let half = angle / T::from_f64(2.0);
+        let axis = axis / axis.length();
+        Self::from_parts(half.cos(), axis * half.sin())
+    }
+
+    /// Build a rotation from Euler angles in radians: first `roll` around x,
+    /// then `pitch` around y, then `yaw` around z, all about the fixed axes.
+    pub fn from_euler(roll: T, pitch: T, yaw: T) -> Self {
+        let x = Vector::new(T::ONE, T::ZERO, T::ZERO);
+        let y = Vector::new(T::ZERO, T::ONE, T::ZERO);
+        let z = Vector::new(T::ZERO, T::ZERO, T::ONE);
+
+        Self::from_axis_angle(&z, yaw)
+            * Self::from_axis_angle(&y, pitch)
+            * Self::from_axis_angle(&x, roll)
+    }
+
+    /// Extract the rotation from a rotation matrix with Shepperd's method,
+    /// which picks the largest diagonal term to divide by.
+    pub fn from_matrix(mat: &Matrix<T>) -> Self {
+        let (r1, r2, r3) = (mat.row(0), mat.row(1), mat.row(2));
+        let trace = mat.trace();
+        let quarter = T::from_f64(0.25);
+        let two = T::from_f64(2.0);
+
+        let q = if trace > T::ZERO {
+            let s = (trace + T::ONE).sqrt() * two; // s = 4w
+            Self::new(
+                quarter * s,
+                (r3.y - r2.z) / s,
+                (r1.z - r3.x) / s,
+                (r2.x - r1.y) / s,
+            )
+        } else if r1.x > r2.y && r1.x > r3.z {
+            let s = (T::ONE + r1.x - r2.y - r3.z).sqrt() * two; // s = 4x
+            Self::new(
+                (r3.y - r2.z) / s,
+                quarter * s,
+                (r1.y + r2.x) / s,
+                (r1.z + r3.x) / s,
+            )
+        } else if r2.y > r3.z {
+            let s = (T::ONE + r2.y - r1.x - r3.z).sqrt() * two; // s = 4y
+            Self::new(
+                (r1.z - r3.x) / s,
+                (r1.y + r2.x) / s,
+                quarter * s,
+                (r2.z + r3.y) / s,
+            )
+        } else {
+            let s = (T::ONE + r3.z - r1.x - r2.y).sqrt() * two; // s = 4z
+            Self::new(
+                (r2.x - r1.y) / s,
+                (r1.z + r3.x) / s,
+                (r2.z + r3.y) / s,
+                quarter * s,
+            )
+        };
+
+        q.normalize()
+    }
+
+    /// Extract the rotation from the linear part of a transform without
+    /// scaling.
+    pub fn from_matrix4(mat: &Matrix4<T>) -> Self {
+        Self::from_matrix(&mat.linear_part())
+    }
+
+    pub fn length(&self) -> T {
+        self.norm_squared().sqrt()
+    }
+
+    pub fn normalize(&self) -> Self {
+        *self * (T::ONE / self.length())
+    }
+
+    /// Normalized linear interpolation along the shortest path. Cheaper than
+    /// `slerp` but the angular speed is not constant.
+    pub fn nlerp(&self, other: &Self, t: T) -> Self {
+        let other = if self.dot_product(other) < T::ZERO {
+            *other * -T::ONE
+        } else {
+            *other
+        };
+
+        (*self * (T::ONE - t) + other * t).normalize()
+    }
+
+    /// Spherical linear interpolation along the shortest path. Both
+    /// quaternions should be unit quaternions.
+    pub fn slerp(&self, other: &Self, t: T) -> Self {
+        let mut cos_theta = self.dot_product(other);
+        let other = if cos_theta < T::ZERO {
+            cos_theta = -cos_theta;
+            *other * -T::ONE
+        } else {
+            *other
+        };
+
+        // nearly parallel, sin(theta) is too small to divide by
+        if cos_theta > T::ONE - T::from_f64(1e-6) {
+            return self.nlerp(&other, t);
+        }
+
+        let theta = cos_theta.acos();
+        let sin_theta = (T::ONE - cos_theta * cos_theta).sqrt();
+        let a = ((T::ONE - t) * theta).sin() / sin_theta;
+        let b = (t * theta).sin() / sin_theta;
+
+        *self * a + other * b
+    }
+}
+
+impl<T: Scalar> Mul<Quaternion<T>> for Quaternion<T> {
+    type Output = Self;
+
+    /// Hamilton product, i.e. the composition of rotations.
+    fn mul(self, rhs: Quaternion<T>) -> Self {
+        Self::from_parts(
+            self.w * rhs.w - self.v.dot_product(&rhs.v),
+            rhs.v * self.w + self.v * rhs.w + self.v.cross_product(&rhs.v),
+        )
+    }
+}
+
+impl<T: Scalar> Mul<&Quaternion<T>> for &Quaternion<T> {
+    type Output = Quaternion<T>;
+
+    /// Hamilton product, i.e. the composition of rotations.
+    fn mul(self, rhs: &Quaternion<T>) -> Quaternion<T> {
+        *self * *rhs
+    }
+}
+
+impl<T: Scalar> Mul<T> for Quaternion<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self {
+        Self::from_parts(self.w * rhs, self.v * rhs)
+    }
+}
+
+impl<T: Scalar> Add for Quaternion<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::from_parts(self.w + rhs.w, self.v + rhs.v)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    use super::Quaternion;
+    use crate::math::{Matrix4, Vector};
+
+    fn assert_close(a: Vector<f64>, b: Vector<f64>) {
+        assert!((a - b).length() < 1e-12, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_rotation() {
+        let axis = Vector::new(1.0, -2.0, 0.5);
+        let q = Quaternion::from_axis_angle(&axis, 0.7);
+        let mat = Matrix4::rotation(&axis, 0.7);
+        let p = Vector::new(0.3, 4.0, -1.0);
+
+        assert_close(q.rotate(&p), mat.transform_direction(&p));
+        assert_close(&q.to_matrix() * p, q.rotate(&p));
+        assert_close(q.inverse().unwrap().rotate(&q.rotate(&p)), p);
+
+        let back = Quaternion::from_matrix4(&q.to_matrix4());
+        assert!((back.dot_product(&q).abs() - 1.0).abs() < 1e-12);
+
+        // a half turn exercises the branches of Shepperd's method
+        for axis in [
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+        ] {
+            let q = Quaternion::from_axis_angle(&axis, PI);
+            let back = Quaternion::from_matrix(&q.to_matrix());
+            assert_close(back.rotate(&p), q.rotate(&p));
+        }
+    }
+
+    #[test]
+    fn test_euler() {
+        let q = Quaternion::<f64>::from_euler(FRAC_PI_2, 0.0, FRAC_PI_2);
+        // roll takes y to z, then yaw leaves z alone
+        assert_close(
+            q.rotate(&Vector::new(0.0, 1.0, 0.0)),
+            Vector::new(0.0, 0.0, 1.0),
+        );
+        // roll leaves x alone, then yaw takes x to y
+        assert_close(
+            q.rotate(&Vector::new(1.0, 0.0, 0.0)),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let z = Vector::new(0.0, 0.0, 1.0);
+        let a = Quaternion::<f64>::identity();
+        let b = Quaternion::from_axis_angle(&z, FRAC_PI_2);
+        let x = Vector::new(1.0, 0.0, 0.0);
+
+        let half = a.slerp(&b, 0.5);
+        assert_close(
+            half.rotate(&x),
+            Vector::new(0.5f64.sqrt(), 0.5f64.sqrt(), 0.0),
+        );
+        let third = a.slerp(&b, 1.0 / 3.0);
+        assert_close(
+            third.rotate(&x),
+            Vector::new((PI / 6.0).cos(), (PI / 6.0).sin(), 0.0),
+        );
+
+        // -b is the same rotation, the shortest path must still be taken
+        let half = a.nlerp(&(b * -1.0), 0.5);
+        assert_close(
+            half.rotate(&x),
+            Vector::new(0.5f64.sqrt(), 0.5f64.sqrt(), 0.0),
+        );
+        assert_close(a.slerp(&b, 1.0).rotate(&x), b.rotate(&x));
+    }
+}
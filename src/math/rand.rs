@@ -5,7 +5,7 @@ mod test {
     #[test]
     fn dice_roll_out() {
         let mut rng = thread_rng();
-    
+
         println!("Dice roll: {}", rng.gen_range(1..=6));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,276 @@
+use std::{
+    cmp::Ordering,
+    fmt::{Display, LowerExp},
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+use super::Scalar;
+
+/// An exact fraction of two `i128`, always reduced with a positive
+/// denominator.
+///
+/// Nothing is ever rounded: an operation whose result does not fit panics
+/// instead, so a result is either exact or absent.
+///
+/// `i128` only goes so far: products of a few fractions with large
+/// denominators overflow. `orient3d` and `incircle` already do for floats
+/// like `0.1`, whose denominator is `2^55`, so use `orient3d_exact` and
+/// `incircle_exact` for float coordinates. Small integers and dyadic
+/// fractions such as `3/4` are fine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Rational {
+    /// Panic if `denominator` is zero.
+    pub fn new(numerator: i128, denominator: i128) -> Self {
+        assert!(denominator != 0, "The denominator should not be zero.");
+        let g = gcd(numerator, denominator);
+        let sign = if denominator < 0 { -1 } else { 1 };
+        Self {
+            numerator: checked(numerator.checked_div(g).and_then(|n| n.checked_mul(sign))),
+            denominator: checked(denominator.checked_div(g).and_then(|d| d.checked_mul(sign))),
+        }
+    }
+
+    pub fn from_integer(value: i128) -> Self {
+        Self::new(value, 1)
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.denominator
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denominator == 1
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    // only 0 / 0 would give 0, and `new` rejects it before
+    checked(i128::try_from(a.max(1)).ok())
+}
+
+fn checked(value: Option<i128>) -> i128 {
+    value.expect("Rational overflow: the result does not fit in i128.")
+}
+
+impl Scalar for Rational {
+    const ZERO: Self = Self {
+        numerator: 0,
+        denominator: 1,
+    };
+    const ONE: Self = Self {
+        numerator: 1,
+        denominator: 1,
+    };
+
+    /// Exact, as every finite `f64` is a fraction with a power of two as
+    /// denominator. Panic if it does not fit, e.g. for `1e-300`.
+    fn from_f64(value: f64) -> Self {
+        assert!(value.is_finite(), "{} is not a rational number.", value);
+        if value == 0.0 {
+            return Self::ZERO;
+        }
+
+        let bits = value.to_bits();
+        let exponent = ((bits >> 52) & 0x7ff) as i32;
+        let fraction = bits & ((1 << 52) - 1);
+        // value = mantissa * 2^exponent
+        let (mantissa, exponent) = if exponent == 0 {
+            (fraction, -1074)
+        } else {
+            (fraction | (1 << 52), exponent - 1075)
+        };
+        let shift = mantissa.trailing_zeros() as i32;
+        let (mantissa, exponent) = ((mantissa >> shift) as i128, exponent + shift);
+        let mantissa = if value < 0.0 { -mantissa } else { mantissa };
+
+        let power = |e: i32| checked(1i128.checked_shl(e as u32).filter(|_| e < 127));
+        if exponent >= 0 {
+            Self::from_integer(checked(mantissa.checked_mul(power(exponent))))
+        } else {
+            Self::new(mantissa, power(-exponent))
+        }
+    }
+
+    /// Rounds, unless both parts are small enough to be exact in `f64`.
+    fn to_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(value: i64) -> Self {
+        Self::from_integer(value as i128)
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // the denominators are positive
+        let lhs = checked(self.numerator.checked_mul(other.denominator));
+        let rhs = checked(other.numerator.checked_mul(self.denominator));
+        lhs.cmp(&rhs)
+    }
+}
+
+impl Neg for Rational {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            numerator: checked(self.numerator.checked_neg()),
+            denominator: self.denominator,
+        }
+    }
+}
+
+impl Add for Rational {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        // over the least common multiple, to delay overflow
+        let g = gcd(self.denominator, rhs.denominator);
+        let (a, b) = (self.denominator / g, rhs.denominator / g);
+        let numerator = checked(
+            self.numerator
+                .checked_mul(b)
+                .zip(rhs.numerator.checked_mul(a))
+                .and_then(|(x, y)| x.checked_add(y)),
+        );
+        Self::new(numerator, checked(self.denominator.checked_mul(b)))
+    }
+}
+
+impl Sub for Rational {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl Mul for Rational {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        // cancel crosswise first, to delay overflow
+        let g1 = gcd(self.numerator, rhs.denominator);
+        let g2 = gcd(rhs.numerator, self.denominator);
+        Self::new(
+            checked((self.numerator / g1).checked_mul(rhs.numerator / g2)),
+            checked((self.denominator / g2).checked_mul(rhs.denominator / g1)),
+        )
+    }
+}
+
+impl Div for Rational {
+    type Output = Self;
+
+    /// Panic if `rhs` is zero.
+    fn div(self, rhs: Self) -> Self {
+        assert!(rhs.numerator != 0, "Division by zero.");
+        self * Self::new(rhs.denominator, rhs.numerator)
+    }
+}
+
+impl AddAssign for Rational {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Rational {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign for Rational {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign for Rational {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = *self / rhs;
+    }
+}
+
+impl Display for Rational {
+    /// `numerator/denominator`, or only the numerator for integers.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+impl LowerExp for Rational {
+    /// Through `f64`, so it may round.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        LowerExp::fmt(&self.to_f64(), f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Rational;
+    use crate::math::{Scalar, Vector};
+
+    #[test]
+    fn test_rational() {
+        let third = Rational::new(2, -6);
+        assert_eq!((third.numerator(), third.denominator()), (-1, 3));
+        assert_eq!(third.to_string(), "-1/3");
+
+        let half = Rational::new(1, 2);
+        assert_eq!(half + third, Rational::new(1, 6));
+        assert_eq!(half - third, Rational::new(5, 6));
+        assert_eq!(half * third, Rational::new(-1, 6));
+        assert_eq!(half / third, Rational::new(-3, 2));
+        assert!(third < Rational::ZERO && Rational::ZERO < half);
+        assert_eq!((half * Rational::from(4)).to_string(), "2");
+
+        // 0.1 is not exactly a tenth
+        let tenth = Rational::from_f64(0.1);
+        assert_eq!(tenth, Rational::new(3602879701896397, 1 << 55));
+        assert_eq!(tenth.to_f64(), 0.1);
+        assert_ne!(tenth * Rational::from(10), Rational::ONE);
+        assert_eq!(Rational::from_f64(-1.5e10), Rational::from(-15_000_000_000));
+
+        let v = Vector::new(half, third, Rational::ONE);
+        assert_eq!(v.dot_product(&v), Rational::new(49, 36));
+        assert_eq!(
+            v.cross_product(&v),
+            Vector::new(Rational::ZERO, Rational::ZERO, Rational::ZERO)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Rational overflow")]
+    fn test_overflow() {
+        let big = Rational::from(i64::MAX);
+        let _ = big * big * big;
+    }
+}
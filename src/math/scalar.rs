@@ -0,0 +1,220 @@
+use std::{
+    fmt::{Debug, Display, LowerExp},
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+/// The number type stored in `Vector` and `Matrix`.
+///
+/// Only the field operations are required here, so exact or fixed-point
+/// number types can implement it as well. Anything that needs square roots
+/// or trigonometry asks for `Real` instead.
+pub trait Scalar:
+    Copy
+    + Debug
+    + Display
+    + LowerExp
+    + PartialEq
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    /// Convert a `f64` constant into this type. Used for the literals that
+    /// appear in the algorithms, so it may round.
+    fn from_f64(value: f64) -> Self;
+
+    fn to_f64(self) -> f64;
+
+    fn abs(self) -> Self {
+        if self < Self::ZERO {
+            -self
+        } else {
+            self
+        }
+    }
+
+    fn min(self, other: Self) -> Self {
+        if other < self {
+            other
+        } else {
+            self
+        }
+    }
+
+    fn max(self, other: Self) -> Self {
+        if other > self {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+/// A `Scalar` that behaves like a floating point number.
+pub trait Real: Scalar {
+    const EPSILON: Self;
+
+    fn sqrt(self) -> Self;
+    fn cbrt(self) -> Self;
+    fn copysign(self, sign: Self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn acos(self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    fn is_finite(self) -> bool;
+    /// The next representable number towards positive infinity.
+    fn next_up(self) -> Self;
+    /// The next representable number towards negative infinity.
+    fn next_down(self) -> Self;
+}
+
+macro_rules! impl_real {
+    ($t: ty) => {
+        impl Scalar for $t {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+
+            #[inline]
+            fn from_f64(value: f64) -> Self {
+                value as $t
+            }
+
+            #[inline]
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            #[inline]
+            fn abs(self) -> Self {
+                <$t>::abs(self)
+            }
+
+            #[inline]
+            fn min(self, other: Self) -> Self {
+                <$t>::min(self, other)
+            }
+
+            #[inline]
+            fn max(self, other: Self) -> Self {
+                <$t>::max(self, other)
+            }
+        }
+
+        impl Real for $t {
+            const EPSILON: Self = <$t>::EPSILON;
+
+            #[inline]
+            fn sqrt(self) -> Self {
+                <$t>::sqrt(self)
+            }
+
+            #[inline]
+            fn cbrt(self) -> Self {
+                <$t>::cbrt(self)
+            }
+
+            #[inline]
+            fn copysign(self, sign: Self) -> Self {
+                <$t>::copysign(self, sign)
+            }
+
+            #[inline]
+            fn powi(self, n: i32) -> Self {
+                <$t>::powi(self, n)
+            }
+
+            #[inline]
+            fn sin(self) -> Self {
+                <$t>::sin(self)
+            }
+
+            #[inline]
+            fn cos(self) -> Self {
+                <$t>::cos(self)
+            }
+
+            #[inline]
+            fn tan(self) -> Self {
+                <$t>::tan(self)
+            }
+
+            #[inline]
+            fn acos(self) -> Self {
+                <$t>::acos(self)
+            }
+
+            #[inline]
+            fn atan2(self, other: Self) -> Self {
+                <$t>::atan2(self, other)
+            }
+
+            #[inline]
+            fn is_finite(self) -> bool {
+                <$t>::is_finite(self)
+            }
+
+            #[inline]
+            fn next_up(self) -> Self {
+                <$t>::next_up(self)
+            }
+
+            #[inline]
+            fn next_down(self) -> Self {
+                <$t>::next_down(self)
+            }
+        }
+    };
+}
+
+impl_real!(f32);
+impl_real!(f64);
+
+/// Integers only make sense where no division is involved, e.g. pixel
+/// coordinates. Division truncates and `from_f64` rounds toward zero.
+macro_rules! impl_integer {
+    ($t: ty) => {
+        impl Scalar for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            #[inline]
+            fn from_f64(value: f64) -> Self {
+                value as $t
+            }
+
+            #[inline]
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            #[inline]
+            fn abs(self) -> Self {
+                <$t>::abs(self)
+            }
+
+            #[inline]
+            fn min(self, other: Self) -> Self {
+                Ord::min(self, other)
+            }
+
+            #[inline]
+            fn max(self, other: Self) -> Self {
+                Ord::max(self, other)
+            }
+        }
+    };
+}
+
+impl_integer!(i32);
+impl_integer!(i64);
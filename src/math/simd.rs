@@ -0,0 +1,218 @@
+use wide::f32x4;
+
+use super::{Matrix, Matrix4, Vector};
+
+#[inline]
+fn to_f32x4(v: &Vector) -> f32x4 {
+    f32x4::from([v.x, v.y, v.z, 0.0])
+}
+
+#[inline]
+fn from_f32x4(v: f32x4) -> Vector {
+    let [x, y, z, _] = v.to_array();
+    Vector::new(x, y, z)
+}
+
+pub fn dot_product(a: &Vector, b: &Vector) -> f32 {
+    (to_f32x4(a) * to_f32x4(b)).reduce_add()
+}
+
+pub fn cross_product(a: &Vector, b: &Vector) -> Vector {
+    // a.yzx * b.zxy - a.zxy * b.yzx
+    let a_yzx = f32x4::from([a.y, a.z, a.x, 0.0]);
+    let a_zxy = f32x4::from([a.z, a.x, a.y, 0.0]);
+    let b_yzx = f32x4::from([b.y, b.z, b.x, 0.0]);
+    let b_zxy = f32x4::from([b.z, b.x, b.y, 0.0]);
+    from_f32x4(a_yzx * b_zxy - a_zxy * b_yzx)
+}
+
+pub fn length(v: &Vector) -> f32 {
+    dot_product(v, v).sqrt()
+}
+
+pub fn normalize(v: &Vector) -> Vector {
+    let v = to_f32x4(v);
+    from_f32x4(v / f32x4::splat((v * v).reduce_add().sqrt()))
+}
+
+/// Matrix multiplication, one row of the result at a time.
+pub fn mul_matrix(a: &Matrix, b: &Matrix) -> Matrix {
+    let b_rows = [0, 1, 2].map(|i| to_f32x4(&b.row(i)));
+    let rows = [0, 1, 2].map(|i| {
+        let row = a.row(i);
+        from_f32x4(
+            f32x4::splat(row.x) * b_rows[0]
+                + f32x4::splat(row.y) * b_rows[1]
+                + f32x4::splat(row.z) * b_rows[2],
+        )
+    });
+    Matrix::from_vectors(rows[0], rows[1], rows[2])
+}
+
+/// Matrix multiplication, one row of the result at a time.
+pub fn mul_matrix4(a: &Matrix4, b: &Matrix4) -> Matrix4 {
+    let b_rows = [0, 1, 2, 3].map(|i| f32x4::from(b.row(i)));
+    let rows = [0, 1, 2, 3].map(|i| {
+        let row = a.row(i);
+        (f32x4::splat(row[0]) * b_rows[0]
+            + f32x4::splat(row[1]) * b_rows[1]
+            + f32x4::splat(row[2]) * b_rows[2]
+            + f32x4::splat(row[3]) * b_rows[3])
+            .to_array()
+    });
+    Matrix4::from_rows(rows)
+}
+
+/// The 8-lane kernels behind `VectorSoa`. The tail that does not fill a
+/// whole register falls back to `batch::scalar`.
+pub(crate) mod soa {
+    use wide::f32x8;
+
+    use crate::math::batch::scalar;
+
+    const LANES: usize = 8;
+
+    #[inline]
+    fn load(slice: &[f32], i: usize) -> f32x8 {
+        f32x8::from(<[f32; LANES]>::try_from(&slice[i..i + LANES]).unwrap())
+    }
+
+    #[inline]
+    fn store(slice: &mut [f32], i: usize, value: f32x8) {
+        slice[i..i + LANES].copy_from_slice(value.as_array_ref());
+    }
+
+    /// The end of the part processed with SIMD.
+    #[inline]
+    fn body_len(len: usize) -> usize {
+        len - len % LANES
+    }
+
+    fn load3(v: [&[f32]; 3], i: usize) -> [f32x8; 3] {
+        [load(v[0], i), load(v[1], i), load(v[2], i)]
+    }
+
+    fn tail(v: [&[f32]; 3], end: usize) -> [&[f32]; 3] {
+        v.map(|each| &each[end..])
+    }
+
+    fn tail_mut(v: [&mut [f32]; 3], end: usize) -> [&mut [f32]; 3] {
+        v.map(|each| &mut each[end..])
+    }
+
+    pub(crate) fn dot(a: [&[f32]; 3], b: [&[f32]; 3], out: &mut [f32]) {
+        let end = body_len(out.len());
+        for i in (0..end).step_by(LANES) {
+            let ([ax, ay, az], [bx, by, bz]) = (load3(a, i), load3(b, i));
+            store(out, i, ax * bx + ay * by + az * bz);
+        }
+        scalar::dot(tail(a, end), tail(b, end), &mut out[end..]);
+    }
+
+    pub(crate) fn cross(a: [&[f32]; 3], b: [&[f32]; 3], out: [&mut [f32]; 3]) {
+        let end = body_len(out[0].len());
+        let [out_x, out_y, out_z] = out;
+        for i in (0..end).step_by(LANES) {
+            let ([ax, ay, az], [bx, by, bz]) = (load3(a, i), load3(b, i));
+            store(out_x, i, ay * bz - az * by);
+            store(out_y, i, az * bx - ax * bz);
+            store(out_z, i, ax * by - ay * bx);
+        }
+        scalar::cross(
+            tail(a, end),
+            tail(b, end),
+            tail_mut([out_x, out_y, out_z], end),
+        );
+    }
+
+    pub(crate) fn length(a: [&[f32]; 3], out: &mut [f32]) {
+        let end = body_len(out.len());
+        for i in (0..end).step_by(LANES) {
+            let [x, y, z] = load3(a, i);
+            store(out, i, (x * x + y * y + z * z).sqrt());
+        }
+        scalar::length(tail(a, end), &mut out[end..]);
+    }
+
+    pub(crate) fn normalize(v: [&mut [f32]; 3]) {
+        let end = body_len(v[0].len());
+        let [x, y, z] = v;
+        for i in (0..end).step_by(LANES) {
+            let [vx, vy, vz] = load3([x, y, z], i);
+            let length = (vx * vx + vy * vy + vz * vz).sqrt();
+            store(x, i, vx / length);
+            store(y, i, vy / length);
+            store(z, i, vz / length);
+        }
+        scalar::normalize(tail_mut([x, y, z], end));
+    }
+
+    pub(crate) fn linear(m: [[f32; 3]; 3], v: [&mut [f32]; 3]) {
+        let end = body_len(v[0].len());
+        let m8 = m.map(|row| row.map(f32x8::splat));
+        let [x, y, z] = v;
+        for i in (0..end).step_by(LANES) {
+            let [vx, vy, vz] = load3([x, y, z], i);
+            store(x, i, m8[0][0] * vx + m8[0][1] * vy + m8[0][2] * vz);
+            store(y, i, m8[1][0] * vx + m8[1][1] * vy + m8[1][2] * vz);
+            store(z, i, m8[2][0] * vx + m8[2][1] * vy + m8[2][2] * vz);
+        }
+        scalar::linear(m, tail_mut([x, y, z], end));
+    }
+
+    pub(crate) fn points(m: [[f32; 4]; 4], v: [&mut [f32]; 3]) {
+        let end = body_len(v[0].len());
+        let m8 = m.map(|row| row.map(f32x8::splat));
+        let [x, y, z] = v;
+        for i in (0..end).step_by(LANES) {
+            let [vx, vy, vz] = load3([x, y, z], i);
+            let w = m8[3][0] * vx + m8[3][1] * vy + m8[3][2] * vz + m8[3][3];
+            store(
+                x,
+                i,
+                (m8[0][0] * vx + m8[0][1] * vy + m8[0][2] * vz + m8[0][3]) / w,
+            );
+            store(
+                y,
+                i,
+                (m8[1][0] * vx + m8[1][1] * vy + m8[1][2] * vz + m8[1][3]) / w,
+            );
+            store(
+                z,
+                i,
+                (m8[2][0] * vx + m8[2][1] * vy + m8[2][2] * vz + m8[2][3]) / w,
+            );
+        }
+        scalar::points(m, tail_mut([x, y, z], end));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{cross_product, dot_product, length, mul_matrix, mul_matrix4, normalize};
+    use crate::math::{Matrix, Matrix4, Vector};
+
+    #[test]
+    fn test_simd_matches_scalar() {
+        let a = Vector::new(1.5, -2.0, 3.25);
+        let b = Vector::new(0.5, 4.0, -1.0);
+
+        assert_eq!(dot_product(&a, &b), a.dot_product(&b));
+        assert_eq!(cross_product(&a, &b), a.cross_product(&b));
+        assert!((length(&a) - a.length()).abs() < 1e-6);
+        assert!((normalize(&a) - a / a.length()).length() < 1e-6);
+
+        let m = Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0);
+        let n = m.transpose();
+        assert_eq!(mul_matrix(&m, &n), &m * &n);
+
+        let p = Matrix4::rotation(&a, 0.4) * Matrix4::translation(&b);
+        let q = Matrix4::perspective(1.0, 2.0, 0.5, 50.0);
+        let (simd, scalar) = (mul_matrix4(&p, &q), &p * &q);
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((simd.row(i)[j] - scalar.row(i)[j]).abs() < 1e-5);
+            }
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use super::{Real, Vector, Vector2};
+
+/// `r` is the length, `theta` the polar angle in `[0, pi]` and `phi` the
+/// azimuth in `[0, 2 pi)`.
+///
+/// As for `Onb`, `z` is the up axis: `theta` is measured from `+z` and `phi`
+/// counter-clockwise from `+x` around it. At the poles, and for zero
+/// vectors, the undefined angles are set to zero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SphericalCoordinates<T = f32> {
+    pub r: T,
+    pub theta: T,
+    pub phi: T,
+}
+
+/// `r` is the length and `theta` the angle from `+x` in `[0, 2 pi)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PolarCoordinates<T = f32> {
+    pub r: T,
+    pub theta: T,
+}
+
+/// `atan2` wrapped into `[0, 2 pi)`, and zero on the axis, where `atan2`
+/// would give `pi` for `x = -0.0`.
+fn azimuth<T: Real>(y: T, x: T) -> T {
+    if x == T::ZERO && y == T::ZERO {
+        return T::ZERO;
+    }
+    let angle = y.atan2(x);
+    if angle < T::ZERO {
+        let wrapped = angle + T::from_f64(std::f64::consts::TAU);
+        // a tiny negative angle would round up to exactly 2 pi
+        if wrapped < T::from_f64(std::f64::consts::TAU) {
+            wrapped
+        } else {
+            T::ZERO
+        }
+    } else {
+        angle
+    }
+}
+
+impl<T: Real> SphericalCoordinates<T> {
+    pub fn from_vector(vector: &Vector<T>) -> Self {
+        let r = vector.length();
+        if r == T::ZERO {
+            return Self {
+                r,
+                theta: T::ZERO,
+                phi: T::ZERO,
+            };
+        }
+        // acos(z / r) loses precision near the poles
+        let rho = (vector.x * vector.x + vector.y * vector.y).sqrt();
+        Self {
+            r,
+            theta: rho.atan2(vector.z),
+            phi: azimuth(vector.y, vector.x),
+        }
+    }
+
+    pub fn to_vector(&self) -> Vector<T> {
+        let (sin_theta, cos_theta) = (self.theta.sin(), self.theta.cos());
+        Vector::new(
+            self.r * sin_theta * self.phi.cos(),
+            self.r * sin_theta * self.phi.sin(),
+            self.r * cos_theta,
+        )
+    }
+}
+
+impl<T: Real> PolarCoordinates<T> {
+    pub fn from_vector(vector: &Vector2<T>) -> Self {
+        Self {
+            r: vector.length(),
+            theta: azimuth(vector.y, vector.x),
+        }
+    }
+
+    pub fn to_vector(&self) -> Vector2<T> {
+        Vector2::new(self.r * self.theta.cos(), self.r * self.theta.sin())
+    }
+}
+
+impl<T: Real> From<Vector<T>> for SphericalCoordinates<T> {
+    fn from(vector: Vector<T>) -> Self {
+        Self::from_vector(&vector)
+    }
+}
+
+impl<T: Real> From<SphericalCoordinates<T>> for Vector<T> {
+    fn from(coordinates: SphericalCoordinates<T>) -> Self {
+        coordinates.to_vector()
+    }
+}
+
+impl<T: Real> From<Vector2<T>> for PolarCoordinates<T> {
+    fn from(vector: Vector2<T>) -> Self {
+        Self::from_vector(&vector)
+    }
+}
+
+impl<T: Real> From<PolarCoordinates<T>> for Vector2<T> {
+    fn from(coordinates: PolarCoordinates<T>) -> Self {
+        coordinates.to_vector()
+    }
+}
+
+impl<T: Real> Vector<T> {
+    /// Octahedral encoding: project onto the octahedron `|x|+|y|+|z| = 1`
+    /// and unfold the lower half over the corners of the square. Only the
+    /// direction is kept, and a zero vector maps to `+z`.
+    ///
+    /// Cigolle et al., "A Survey of Efficient Representations for
+    /// Independent Unit Vectors", 2014.
+    pub fn to_octahedral(&self) -> Vector2<T> {
+        let norm = self.x.abs() + self.y.abs() + self.z.abs();
+        if norm == T::ZERO {
+            return Vector2::new(T::ZERO, T::ZERO);
+        }
+        let p = Vector2::new(self.x / norm, self.y / norm);
+        if self.z < T::ZERO {
+            fold(&p)
+        } else {
+            p
+        }
+    }
+
+    /// Invert `to_octahedral`, giving a unit vector.
+    pub fn from_octahedral(p: &Vector2<T>) -> Self {
+        let z = T::ONE - p.x.abs() - p.y.abs();
+        let p = if z < T::ZERO { fold(p) } else { *p };
+        Self::new(p.x, p.y, z).normalize()
+    }
+
+    /// Equal-area encoding: uniformly distributed points of the square map
+    /// to uniformly distributed directions and back, with the same layout as
+    /// `to_octahedral`. Only the direction is kept, and a zero vector maps
+    /// to `+z`.
+    ///
+    /// Clarberg, "Fast Equal-Area Mapping of the (Hemi)Sphere using SIMD",
+    /// 2008.
+    pub fn to_equal_area(&self) -> Vector2<T> {
+        let length = self.length();
+        if length == T::ZERO {
+            return Vector2::new(T::ZERO, T::ZERO);
+        }
+        let (x, y, z) = (
+            (self.x / length).abs(),
+            (self.y / length).abs(),
+            (self.z / length).abs(),
+        );
+
+        // distance from the centre of the square, in L1
+        let r = (T::ONE - z).max(T::ZERO).sqrt();
+        // where on that diamond, from the x axis to the y axis, in [0, 1]
+        let (a, b) = (x.max(y), x.min(y));
+        let t = if a == T::ZERO {
+            // at the poles any t gives the same point
+            T::ZERO
+        } else {
+            (b / a).atan2(T::ONE) / T::from_f64(std::f64::consts::FRAC_PI_2)
+        };
+        let t = if x < y { T::ONE - t } else { t };
+
+        let v = t * r;
+        let u = r - v;
+        let (u, v) = if self.z < T::ZERO {
+            (T::ONE - v, T::ONE - u)
+        } else {
+            (u, v)
+        };
+        Vector2::new(u.copysign(self.x), v.copysign(self.y))
+    }
+
+    /// Invert `to_equal_area`, giving a unit vector.
+    pub fn from_equal_area(p: &Vector2<T>) -> Self {
+        let (u, v) = (p.x.abs(), p.y.abs());
+        let signed_distance = T::ONE - (u + v);
+        let r = T::ONE - signed_distance.abs();
+        let t = if r == T::ZERO {
+            T::ZERO
+        } else {
+            (v - u) / r + T::ONE
+        };
+        let phi = t * T::from_f64(std::f64::consts::FRAC_PI_4);
+
+        let z = (T::ONE - r * r).copysign(signed_distance);
+        let s = r * (T::from_f64(2.0) - r * r).max(T::ZERO).sqrt();
+        Self::new(
+            (s * phi.cos()).copysign(p.x),
+            (s * phi.sin()).copysign(p.y),
+            z,
+        )
+    }
+}
+
+/// Reflect across the edge of the diamond `|x| + |y| = 1`, towards the
+/// corner of the quadrant.
+fn fold<T: Real>(p: &Vector2<T>) -> Vector2<T> {
+    Vector2::new(
+        (T::ONE - p.y.abs()).copysign(p.x),
+        (T::ONE - p.x.abs()).copysign(p.y),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::{PolarCoordinates, SphericalCoordinates};
+    use crate::{
+        assert_approx_eq,
+        math::{Tolerance, Vector, Vector2},
+    };
+
+    const TOLERANCE: Tolerance<f64> = Tolerance::Absolute(1e-12);
+
+    fn random_direction(rng: &mut impl Rng) -> Vector<f64> {
+        loop {
+            let v = Vector::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+            let length = v.length();
+            if length > 1e-3 && length <= 1.0 {
+                return v / length;
+            }
+        }
+    }
+
+    /// The poles, the axes and the edges where the unfolding happens.
+    fn special_directions() -> Vec<Vector<f64>> {
+        let mut directions = vec![];
+        for (x, y, z) in [
+            (0.0, 0.0, 1.0),
+            (0.0, 0.0, -1.0),
+            (-0.0, -0.0, -1.0),
+            (1.0, 0.0, 0.0),
+            (-1.0, 0.0, 0.0),
+            (0.0, 1.0, 0.0),
+            (0.0, -1.0, 0.0),
+            (1.0, 1.0, 0.0),
+            (-1.0, 1.0, -1.0),
+            (1e-9, 0.0, -1.0),
+            (0.0, -1e-9, 1.0),
+        ] {
+            directions.push(Vector::new(x, y, z).normalize());
+        }
+        directions
+    }
+
+    #[test]
+    fn test_spherical() {
+        let s = SphericalCoordinates::from_vector(&Vector::new(0.0, -2.0, 0.0));
+        assert_eq!(s.r, 2.0);
+        assert_approx_eq!(s.theta, FRAC_PI_2, TOLERANCE);
+        assert_approx_eq!(s.phi, 1.5 * PI, TOLERANCE);
+
+        for (v, theta) in [
+            (Vector::new(0.0, 0.0, 3.0), 0.0),
+            (Vector::new(-0.0, 0.0, -3.0), PI),
+        ] {
+            let s = SphericalCoordinates::from(v);
+            assert_eq!((s.r, s.theta, s.phi), (3.0, theta, 0.0));
+            assert_approx_eq!(Vector::from(s), v, TOLERANCE);
+        }
+        let zero = SphericalCoordinates::from_vector(&Vector::new(0.0, 0.0, 0.0));
+        assert_eq!((zero.r, zero.theta, zero.phi), (0.0, 0.0, 0.0));
+
+        // precise near the poles, where acos would not be
+        let s = SphericalCoordinates::from_vector(&Vector::new(1e-10, 0.0, 1.0));
+        assert_approx_eq!(s.theta, 1e-10, Tolerance::relative(1e-12));
+
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..1000 {
+            let v = random_direction(&mut rng) * rng.gen_range(0.1..10.0);
+            let s = SphericalCoordinates::from(v);
+            assert!((0.0..=PI).contains(&s.theta) && (0.0..2.0 * PI).contains(&s.phi));
+            assert_approx_eq!(s.to_vector(), v, TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn test_polar() {
+        let p = PolarCoordinates::from_vector(&Vector2::new(-1.0, -1.0));
+        assert_approx_eq!(p.r, 2f64.sqrt(), TOLERANCE);
+        assert_approx_eq!(p.theta, 1.25 * PI, TOLERANCE);
+        assert_eq!(PolarCoordinates::from(Vector2::new(-0.0, 0.0)).theta, 0.0);
+        assert_eq!(
+            PolarCoordinates::from(Vector2::new(1.0, -1e-300)).theta,
+            0.0
+        );
+
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..1000 {
+            let v = Vector2::new(rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0));
+            let p = PolarCoordinates::from(v);
+            assert!((0.0..2.0 * PI).contains(&p.theta));
+            let back = Vector2::from(p);
+            assert!((back - v).length() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_octahedral() {
+        assert_eq!(
+            Vector::new(0.0, 0.0, 2.0).to_octahedral(),
+            Vector2::new(0.0, 0.0)
+        );
+        assert_eq!(
+            Vector::new(3.0, 0.0, 0.0).to_octahedral(),
+            Vector2::new(1.0, 0.0)
+        );
+        assert_eq!(
+            Vector::new(0.0, 0.0, -1.0).to_octahedral().abs(),
+            Vector2::new(1.0, 1.0)
+        );
+        assert_eq!(
+            Vector::new(0.0, 0.0, 0.0).to_octahedral(),
+            Vector2::new(0.0, 0.0)
+        );
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let directions = special_directions()
+            .into_iter()
+            .chain((0..1000).map(|_| random_direction(&mut rng)));
+        for v in directions {
+            let p = v.to_octahedral();
+            assert!(p.x.abs() <= 1.0 && p.y.abs() <= 1.0);
+            assert_approx_eq!(Vector::from_octahedral(&p), v, TOLERANCE);
+        }
+        // every corner is the south pole
+        for (x, y) in [(1.0, 1.0), (-1.0, 1.0), (1.0, -1.0), (-1.0, -1.0)] {
+            let v = Vector::from_octahedral(&Vector2::new(x, y));
+            assert_approx_eq!(v, Vector::new(0.0, 0.0, -1.0), TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn test_equal_area() {
+        assert_eq!(
+            Vector::new(0.0, 0.0, 2.0).to_equal_area(),
+            Vector2::new(0.0, 0.0)
+        );
+        assert_eq!(
+            Vector::new(0.0, 0.0, 0.0).to_equal_area(),
+            Vector2::new(0.0, 0.0)
+        );
+        assert_approx_eq!(
+            Vector::new(0.0, -1.0, 0.0).to_equal_area(),
+            Vector2::new(0.0, -1.0),
+            TOLERANCE
+        );
+
+        let mut rng = StdRng::seed_from_u64(4);
+        let directions = special_directions()
+            .into_iter()
+            .chain((0..1000).map(|_| random_direction(&mut rng)));
+        for v in directions {
+            let p = v.to_equal_area();
+            assert!(p.x.abs() <= 1.0 && p.y.abs() <= 1.0);
+            assert_approx_eq!(Vector::from_equal_area(&p), v, Tolerance::Absolute(1e-9));
+        }
+
+        // the cap z > 0.5 is a quarter of the sphere, so it should get about
+        // a quarter of uniform samples of the square
+        let n = 100_000;
+        let in_cap = (0..n)
+            .map(|_| Vector2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)))
+            .filter(|p| Vector::from_equal_area(p).z > 0.5)
+            .count();
+        assert!((in_cap as f64 / n as f64 - 0.25).abs() < 0.01);
+    }
+}
@@ -1,12 +1,64 @@
-use super::Vector;
-
-pub fn root_mean_square(w: &Vector, u: &Vector, v: &Vector) -> f32 {
-    (((w.length() - 1.0).powi(2)
-        + (u.length() - 1.0).powi(2)
-        + (v.length() - 1.0).powi(2)
-        + w.dot_product(&u).powi(2)
-        + w.dot_product(&v).powi(2)
+use std::fmt::Display;
+
+use super::{BasisMethod, Onb, Real, Vector};
+
+pub fn root_mean_square<T: Real>(w: &Vector<T>, u: &Vector<T>, v: &Vector<T>) -> T {
+    (((w.length() - T::ONE).powi(2)
+        + (u.length() - T::ONE).powi(2)
+        + (v.length() - T::ONE).powi(2)
+        + w.dot_product(u).powi(2)
+        + w.dot_product(v).powi(2)
         + u.dot_product(v).powi(2))
-        / 6.0)
-        .sqrt()
+        / T::from_f64(6.0))
+    .sqrt()
+}
+
+/// The maximum and the root mean square of the `root_mean_square` error
+/// over a set of bases.
+#[derive(Debug, Clone, Copy)]
+pub struct BasisErrorReport<T = f32> {
+    pub samples: usize,
+    pub max: T,
+    pub rms: T,
+}
+
+impl<T: Real> Display for BasisErrorReport<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "samples: {}, max error: {:e}, rms error: {:e}",
+            self.samples,
+            self.max.to_f64(),
+            self.rms.to_f64()
+        ))
+    }
+}
+
+/// Build a basis around each of `directions` with `method` and measure how
+/// far it is from being orthonormal. Zero vectors are skipped.
+pub fn basis_error_report<T: Real>(
+    directions: impl IntoIterator<Item = Vector<T>>,
+    method: BasisMethod,
+) -> BasisErrorReport<T> {
+    let mut samples = 0;
+    let mut max = T::ZERO;
+    let mut sum_squares = T::ZERO;
+    for direction in directions {
+        if let Some(onb) = Onb::from_w(&direction, method) {
+            let error = root_mean_square(&onb.w, &onb.u, &onb.v);
+            // NaN and infinity have to stay as the worst error
+            if error > max || !error.is_finite() {
+                max = error;
+            }
+            sum_squares += error * error;
+            samples += 1;
+        }
+    }
+
+    let rms = if samples == 0 {
+        T::ZERO
+    } else {
+        (sum_squares / T::from_f64(samples as f64)).sqrt()
+    };
+
+    BasisErrorReport { samples, max, rms }
 }
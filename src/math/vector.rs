@@ -1,21 +1,22 @@
-use std::ops::Div;
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
+};
 
-#[derive(Debug, Clone, Copy)]
-pub struct Vector {
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
+use super::{Real, Scalar};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vector<T = f32> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
 }
 
-impl Vector {
-    pub fn new(x: f32, y: f32, z: f32) -> Vector {
+impl<T: Scalar> Vector<T> {
+    pub fn new(x: T, y: T, z: T) -> Self {
         Vector { x, y, z }
     }
 
-    pub fn length(&self) -> f32 {
-        (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
-    }
-
     pub fn cross_product(&self, other: &Self) -> Self {
         Vector::new(
             self.y * other.z - self.z * other.y,
@@ -24,35 +25,43 @@ impl Vector {
         )
     }
 
-    pub fn dot_product(&self, other: &Self) -> f32 {
+    pub fn dot_product(&self, other: &Self) -> T {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
+}
+
+impl<T: Real> Vector<T> {
+    pub fn length(&self) -> T {
+        (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+
+    pub fn normalize(&self) -> Self {
+        self / self.length()
+    }
 
     pub fn orthonormal_basis_simple(&self) -> Option<(Self, Self)> {
         let w_len = self.length();
-        if w_len == 0.0 {
+        if w_len == T::ZERO {
             return None;
         }
         let w = self / w_len;
 
-        let mut t = w.clone();
+        let mut t = w;
         let (t_x, t_y, t_z) = (t.x.abs(), t.y.abs(), t.z.abs());
         if t_x <= t_y {
             if t_x <= t_z {
-                t.x = 1.0;
+                t.x = T::ONE;
             } else {
-                t.z = 1.0;
+                t.z = T::ONE;
             }
+        } else if t_y <= t_z {
+            t.y = T::ONE;
         } else {
-            if t_y <= t_z {
-                t.y = 1.0;
-            } else {
-                t.z = 1.0;
-            }
+            t.z = T::ONE;
         }
 
         let mut u = w.cross_product(&t);
-        u.div_(u.length());
+        u /= u.length();
 
         let v = w.cross_product(&u);
 
@@ -62,15 +71,18 @@ impl Vector {
     /// Get the orthonormal basis from current vector. The vector itself
     /// should be a unit vector.
     pub fn orthonormal_basis_frisvad_from_unit(&self) -> (Self, Self) {
-        if self.z < -0.9999999 {
-            return (Self::new(0.0, -1.0, 0.0), Self::new(-1.0, 0.0, 0.0));
+        if self.z < T::from_f64(-0.9999999) {
+            return (
+                Self::new(T::ZERO, -T::ONE, T::ZERO),
+                Self::new(-T::ONE, T::ZERO, T::ZERO),
+            );
         }
 
-        let a = 1.0 / (1.0 + self.z);
+        let a = T::ONE / (T::ONE + self.z);
         let b = -self.x * self.y * a;
         (
-            Self::new(1.0 - self.x * self.x * a, b, -self.x),
-            Self::new(b, 1.0 - self.y * self.y * a, -self.y),
+            Self::new(T::ONE - self.x * self.x * a, b, -self.x),
+            Self::new(b, T::ONE - self.y * self.y * a, -self.y),
         )
     }
 
@@ -79,7 +91,7 @@ impl Vector {
     /// `orthonormal_basis_frisvad_from_unit` function.
     pub fn orthonormal_basis_frisvad(&self) -> Option<(Self, Self)> {
         let w_len = self.length();
-        if w_len == 0.0 {
+        if w_len == T::ZERO {
             return None;
         }
         let w = self / w_len;
@@ -91,11 +103,15 @@ impl Vector {
     /// should be a unit vector.
     #[inline]
     pub fn orthonormal_basis_revised_from_unit(&self) -> (Self, Self) {
-        let sign = 1.0f32.copysign(self.z);
-        let a = -1.0 / (sign + self.z);
+        let sign = T::ONE.copysign(self.z);
+        let a = -T::ONE / (sign + self.z);
         let b = self.x * self.y * a;
         (
-            Self::new(1.0 + sign * self.x * self.x * a, sign * b, -sign * self.x),
+            Self::new(
+                T::ONE + sign * self.x * self.x * a,
+                sign * b,
+                -sign * self.x,
+            ),
             Self::new(b, sign + self.y * self.y * a, -self.y),
         )
     }
@@ -106,7 +122,7 @@ impl Vector {
     #[inline]
     pub fn orthonormal_basis_revised(&self) -> Option<(Self, Self)> {
         let w_len = self.length();
-        if w_len == 0.0 {
+        if w_len == T::ZERO {
             return None;
         }
         let w = self / w_len;
@@ -115,30 +131,344 @@ impl Vector {
     }
 }
 
-impl Div<f32> for Vector {
-    type Output = Self;
+/// Implement the component-wise operators shared by `Vector`, `Vector2` and
+/// `Vector4`, for owned and borrowed operands.
+macro_rules! impl_vector_ops {
+    ($name: ident { $($field: ident),+ }) => {
+        impl<T: Scalar> $name<T> {
+            /// Multiply two vectors component by component.
+            pub fn component_mul(&self, other: &Self) -> Self {
+                Self { $($field: self.$field * other.$field),+ }
+            }
+
+            /// Component-wise minimum of two vectors.
+            pub fn component_min(&self, other: &Self) -> Self {
+                Self { $($field: self.$field.min(other.$field)),+ }
+            }
+
+            /// Component-wise maximum of two vectors.
+            pub fn component_max(&self, other: &Self) -> Self {
+                Self { $($field: self.$field.max(other.$field)),+ }
+            }
+
+            /// Absolute value of every component.
+            pub fn abs(&self) -> Self {
+                Self { $($field: self.$field.abs()),+ }
+            }
+        }
+
+        impl<T: Scalar> std::fmt::Display for $name<T> {
+            /// Print as a row, see the `format` module for the options.
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                $crate::math::format::write_vector(
+                    f,
+                    &[$(self.$field),+],
+                    $crate::math::format::Notation::Decimal,
+                )
+            }
+        }
+
+        impl<T: Scalar> std::fmt::LowerExp for $name<T> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                $crate::math::format::write_vector(
+                    f,
+                    &[$(self.$field),+],
+                    $crate::math::format::Notation::Exponent,
+                )
+            }
+        }
+
+        impl<T: Scalar + $crate::math::ApproxEq<T>> $crate::math::ApproxEq<T> for $name<T> {
+            fn approx_eq(&self, other: &Self, tolerance: $crate::math::Tolerance<T>) -> bool {
+                $($crate::math::ApproxEq::approx_eq(&self.$field, &other.$field, tolerance))&&+
+            }
+        }
+
+        impl_vector_ops!(@binary $name { $($field),+ } Add, add, +);
+        impl_vector_ops!(@binary $name { $($field),+ } Sub, sub, -);
+        impl_vector_ops!(@assign $name { $($field),+ } AddAssign, add_assign, +=);
+        impl_vector_ops!(@assign $name { $($field),+ } SubAssign, sub_assign, -=);
+        impl_vector_ops!(@scalar $name { $($field),+ } Mul, mul, *);
+        impl_vector_ops!(@scalar $name { $($field),+ } Div, div, /);
+
+        impl<T: Scalar> Neg for $name<T> {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self { $($field: -self.$field),+ }
+            }
+        }
+
+        impl<T: Scalar> Neg for &$name<T> {
+            type Output = $name<T>;
+
+            fn neg(self) -> Self::Output {
+                -*self
+            }
+        }
+
+        impl<T: Scalar> MulAssign<T> for $name<T> {
+            fn mul_assign(&mut self, rhs: T) {
+                $(self.$field *= rhs;)+
+            }
+        }
+
+        impl<T: Scalar> DivAssign<T> for $name<T> {
+            fn div_assign(&mut self, rhs: T) {
+                $(self.$field /= rhs;)+
+            }
+        }
+
+        // `scalar * vector` can not be implemented generically because of
+        // the orphan rule, so it is written out for the primitive floats.
+        impl_vector_ops!(@scalar_lhs $name f32);
+        impl_vector_ops!(@scalar_lhs $name f64);
+    };
+
+    (@binary $name: ident { $($field: ident),+ } $trait: ident, $method: ident, $op: tt) => {
+        impl<T: Scalar> $trait<&$name<T>> for &$name<T> {
+            type Output = $name<T>;
+
+            fn $method(self, rhs: &$name<T>) -> $name<T> {
+                $name { $($field: self.$field $op rhs.$field),+ }
+            }
+        }
+
+        impl<T: Scalar> $trait<$name<T>> for &$name<T> {
+            type Output = $name<T>;
+
+            fn $method(self, rhs: $name<T>) -> $name<T> {
+                self $op &rhs
+            }
+        }
+
+        impl<T: Scalar> $trait<&$name<T>> for $name<T> {
+            type Output = $name<T>;
+
+            fn $method(self, rhs: &$name<T>) -> $name<T> {
+                &self $op rhs
+            }
+        }
+
+        impl<T: Scalar> $trait<$name<T>> for $name<T> {
+            type Output = $name<T>;
+
+            fn $method(self, rhs: $name<T>) -> $name<T> {
+                &self $op &rhs
+            }
+        }
+    };
+
+    (@assign $name: ident { $($field: ident),+ } $trait: ident, $method: ident, $op: tt) => {
+        impl<T: Scalar> $trait<&$name<T>> for $name<T> {
+            fn $method(&mut self, rhs: &$name<T>) {
+                $(self.$field $op rhs.$field;)+
+            }
+        }
+
+        impl<T: Scalar> $trait<$name<T>> for $name<T> {
+            fn $method(&mut self, rhs: $name<T>) {
+                *self $op &rhs;
+            }
+        }
+    };
+
+    (@scalar $name: ident { $($field: ident),+ } $trait: ident, $method: ident, $op: tt) => {
+        impl<T: Scalar> $trait<T> for $name<T> {
+            type Output = Self;
+
+            fn $method(self, rhs: T) -> Self {
+                Self { $($field: self.$field $op rhs),+ }
+            }
+        }
+
+        impl<T: Scalar> $trait<T> for &$name<T> {
+            type Output = $name<T>;
+
+            fn $method(self, rhs: T) -> Self::Output {
+                *self $op rhs
+            }
+        }
+    };
+
+    (@scalar_lhs $name: ident $t: ty) => {
+        impl Mul<$name<$t>> for $t {
+            type Output = $name<$t>;
+
+            fn mul(self, rhs: $name<$t>) -> $name<$t> {
+                rhs * self
+            }
+        }
+
+        impl Mul<&$name<$t>> for $t {
+            type Output = $name<$t>;
+
+            fn mul(self, rhs: &$name<$t>) -> $name<$t> {
+                rhs * self
+            }
+        }
+    };
+}
+
+pub(super) use impl_vector_ops;
+
+impl_vector_ops!(Vector { x, y, z });
+
+impl<T> From<[T; 3]> for Vector<T> {
+    fn from([x, y, z]: [T; 3]) -> Self {
+        Vector { x, y, z }
+    }
+}
 
-    fn div(self, rhs: f32) -> Self {
-        Self::new(self.x / rhs, self.y / rhs, self.z / rhs)
+impl<T> From<Vector<T>> for [T; 3] {
+    fn from(v: Vector<T>) -> Self {
+        [v.x, v.y, v.z]
     }
 }
 
-impl Div<f32> for &Vector {
-    type Output = Vector;
+impl<T> Index<usize> for Vector<T> {
+    type Output = T;
 
-    fn div(self, rhs: f32) -> Self::Output {
-        Vector::new(self.x / rhs, self.y / rhs, self.z / rhs)
+    /// Index the components by axis: 0 for x, 1 for y and 2 for z.
+    fn index(&self, index: usize) -> &T {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!(
+                "Index error: axis should be in [0, 2], current index is {}",
+                index
+            ),
+        }
     }
 }
 
-impl Vector {
-    fn div_(&mut self, rhs: f32) -> &Self {
-        self.x = self.x / rhs;
-        self.y = self.y / rhs;
-        self.z = self.z / rhs;
-        self
+impl<T> IndexMut<usize> for Vector<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!(
+                "Index error: axis should be in [0, 2], current index is {}",
+                index
+            ),
+        }
     }
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use std::{f64::consts::PI, time::Instant};
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::Vector;
+    use crate::math::{
+        utils::{basis_error_report, root_mean_square},
+        BasisMethod,
+    };
+
+    /// Uniformly distributed unit vectors, followed by the same number of
+    /// unit vectors close to the -z pole at several distances.
+    fn sample_directions(count: usize) -> Vec<Vector> {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        let mut directions = Vec::with_capacity(2 * count);
+
+        for _ in 0..count {
+            let z: f64 = rng.gen_range(-1.0..=1.0);
+            let phi = rng.gen_range(0.0..2.0 * PI);
+            let r = (1.0 - z * z).sqrt();
+            directions.push(Vector::new(r * phi.cos(), r * phi.sin(), z));
+        }
+        for i in 0..count {
+            // 1 + z from 1e-1 down to 1e-9
+            let distance = 10f64.powi(-((i % 9) as i32 + 1)) * rng.gen_range(0.5..1.0);
+            let z = distance - 1.0;
+            let phi = rng.gen_range(0.0..2.0 * PI);
+            let r = (1.0 - z * z).sqrt();
+            directions.push(Vector::new(r * phi.cos(), r * phi.sin(), z));
+        }
+
+        directions
+            .into_iter()
+            .map(|d: Vector<f64>| {
+                let d = Vector::new(d.x as f32, d.y as f32, d.z as f32);
+                d / d.length()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_basis_f64() {
+        let w = Vector::<f64>::new(0.3, -0.4, 0.5);
+        let (u, v) = w.orthonormal_basis_revised().unwrap();
+        let w = w / w.length();
+
+        assert!(root_mean_square(&w, &u, &v) < 1e-12);
+    }
+
+    /// Run with `cargo test --release basis_accuracy -- --nocapture` to see
+    /// the report.
+    #[test]
+    fn test_basis_accuracy() {
+        let directions = sample_directions(1 << 20);
+
+        let mut reports = vec![];
+        for method in [
+            BasisMethod::Simple,
+            BasisMethod::Frisvad,
+            BasisMethod::Revised,
+        ] {
+            let start = Instant::now();
+            let report = basis_error_report(directions.iter().copied(), method);
+            println!("{:?}: {} ({:?})", method, report, start.elapsed());
+            reports.push(report);
+        }
+
+        let revised = reports[2];
+        assert_eq!(revised.samples, directions.len());
+        assert!(revised.max < 1e-6, "revised: {}", revised);
+        assert!(revised.rms < 1e-7, "revised: {}", revised);
+        // the pole samples are exactly where Frisvad's method breaks down
+        assert!(reports[1].max > 100.0 * revised.max);
+    }
+
+    #[test]
+    fn test_operators() {
+        let a = Vector::new(1.0, 2.0, 3.0);
+        let b = Vector::new(-4.0, 0.5, 2.0);
+
+        let sum = a + b;
+        assert_eq!((sum.x, sum.y, sum.z), (-3.0, 2.5, 5.0));
+        let diff = a - b;
+        assert_eq!((diff.x, diff.y, diff.z), (5.0, 1.5, 1.0));
+        let scaled = 2.0f32 * -a;
+        assert_eq!((scaled.x, scaled.y, scaled.z), (-2.0, -4.0, -6.0));
+
+        let mut c = a;
+        c += b;
+        c -= &a;
+        c *= 2.0;
+        c /= 4.0;
+        assert_eq!((c[0], c[1], c[2]), (-2.0, 0.25, 1.0));
+        c[1] = 7.0;
+        assert_eq!(c.y, 7.0);
+
+        let m = a.component_mul(&b);
+        assert_eq!((m.x, m.y, m.z), (-4.0, 1.0, 6.0));
+        let lo = a.component_min(&b);
+        assert_eq!((lo.x, lo.y, lo.z), (-4.0, 0.5, 2.0));
+        let hi = a.component_max(&b);
+        assert_eq!((hi.x, hi.y, hi.z), (1.0, 2.0, 3.0));
+        let abs = b.abs();
+        assert_eq!((abs.x, abs.y, abs.z), (4.0, 0.5, 2.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_out_of_range() {
+        let a = Vector::new(1.0, 2.0, 3.0);
+        let _ = a[3];
+    }
+}
@@ -0,0 +1,139 @@
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
+};
+
+use super::{vector::impl_vector_ops, Real, Scalar, Vector};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vector2<T = f32> {
+    pub x: T,
+    pub y: T,
+}
+
+/// Integer pixel coordinates.
+pub type Vector2i = Vector2<i32>;
+
+impl<T: Scalar> Vector2<T> {
+    pub fn new(x: T, y: T) -> Self {
+        Vector2 { x, y }
+    }
+
+    pub fn dot_product(&self, other: &Self) -> T {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The z component of the cross product of the two vectors extended to
+    /// 3D. Positive if `other` is counter-clockwise from `self`.
+    pub fn perp_dot_product(&self, other: &Self) -> T {
+        self.x * other.y - self.y * other.x
+    }
+
+    /// Rotate by 90 degrees counter-clockwise.
+    pub fn perpendicular(&self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    pub fn yx(&self) -> Self {
+        Self::new(self.y, self.x)
+    }
+
+    /// Append a z component.
+    pub fn extend(&self, z: T) -> Vector<T> {
+        Vector::new(self.x, self.y, z)
+    }
+
+    /// Convert to another scalar type through `f64`, e.g. from pixel
+    /// coordinates to `f32`. Converting to integers rounds toward zero.
+    pub fn cast<U: Scalar>(&self) -> Vector2<U> {
+        Vector2::new(U::from_f64(self.x.to_f64()), U::from_f64(self.y.to_f64()))
+    }
+}
+
+impl<T: Real> Vector2<T> {
+    pub fn length(&self) -> T {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    pub fn normalize(&self) -> Self {
+        self / self.length()
+    }
+}
+
+impl<T: Scalar> Vector<T> {
+    pub fn xy(&self) -> Vector2<T> {
+        Vector2::new(self.x, self.y)
+    }
+
+    pub fn xz(&self) -> Vector2<T> {
+        Vector2::new(self.x, self.z)
+    }
+
+    pub fn yz(&self) -> Vector2<T> {
+        Vector2::new(self.y, self.z)
+    }
+}
+
+impl_vector_ops!(Vector2 { x, y });
+
+impl<T> Index<usize> for Vector2<T> {
+    type Output = T;
+
+    /// Index the components by axis: 0 for x and 1 for y.
+    fn index(&self, index: usize) -> &T {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            _ => panic!(
+                "Index error: axis should be in [0, 1], current index is {}",
+                index
+            ),
+        }
+    }
+}
+
+impl<T> IndexMut<usize> for Vector2<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            _ => panic!(
+                "Index error: axis should be in [0, 1], current index is {}",
+                index
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Vector2, Vector2i};
+    use crate::math::Vector;
+
+    #[test]
+    fn test_vector2() {
+        let a = Vector2::new(3.0, 4.0);
+        assert_eq!(a.length(), 5.0);
+        assert_eq!(a.normalize(), Vector2::new(0.6, 0.8));
+        assert_eq!(a.dot_product(&a.perpendicular()), 0.0);
+        assert_eq!(a.perp_dot_product(&a.perpendicular()), 25.0);
+        assert_eq!(2.0f32 * a - a.yx(), Vector2::new(2.0, 5.0));
+
+        let v = Vector::new(1.0, 2.0, 3.0);
+        assert_eq!(v.yz().extend(v.x), Vector::new(2.0, 3.0, 1.0));
+        assert_eq!(v.xz()[1], 3.0);
+    }
+
+    #[test]
+    fn test_pixel_coordinates() {
+        let pixel = Vector2i::new(5, -2);
+        let neighbor = pixel + Vector2i::new(-1, 1);
+        assert_eq!(neighbor, Vector2::new(4, -1));
+        assert_eq!((neighbor * 2).abs(), Vector2::new(8, 2));
+        assert_eq!(pixel.component_max(&neighbor), Vector2::new(5, -1));
+
+        let center = pixel.cast::<f32>() + Vector2::new(0.5, 0.5);
+        assert_eq!(center, Vector2::new(5.5, -1.5));
+        assert_eq!(center.cast::<i32>(), Vector2::new(5, -1));
+    }
+}
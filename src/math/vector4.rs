@@ -0,0 +1,160 @@
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign,
+};
+
+use super::{vector::impl_vector_ops, Matrix4, Real, Scalar, Vector, Vector2};
+
+/// A homogeneous coordinate or any other 4-component value, e.g. RGBA.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vector4<T = f32> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+    pub w: T,
+}
+
+impl<T: Scalar> Vector4<T> {
+    pub fn new(x: T, y: T, z: T, w: T) -> Self {
+        Vector4 { x, y, z, w }
+    }
+
+    /// The homogeneous coordinate of a point, i.e. `w = 1`.
+    pub fn point(point: &Vector<T>) -> Self {
+        point.extend(T::ONE)
+    }
+
+    /// The homogeneous coordinate of a direction, i.e. `w = 0`.
+    pub fn direction(direction: &Vector<T>) -> Self {
+        direction.extend(T::ZERO)
+    }
+
+    pub fn dot_product(&self, other: &Self) -> T {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn xy(&self) -> Vector2<T> {
+        Vector2::new(self.x, self.y)
+    }
+
+    /// Drop the w component.
+    pub fn xyz(&self) -> Vector<T> {
+        Vector::new(self.x, self.y, self.z)
+    }
+
+    /// Divide by w to get back a point, e.g. after a projection.
+    pub fn homogeneous_divide(&self) -> Vector<T> {
+        Vector::new(self.x / self.w, self.y / self.w, self.z / self.w)
+    }
+
+    pub fn to_array(&self) -> [T; 4] {
+        [self.x, self.y, self.z, self.w]
+    }
+}
+
+impl<T: Real> Vector4<T> {
+    pub fn length(&self) -> T {
+        self.dot_product(self).sqrt()
+    }
+
+    pub fn normalize(&self) -> Self {
+        self / self.length()
+    }
+}
+
+impl<T: Scalar> Vector<T> {
+    /// Append a w component.
+    pub fn extend(&self, w: T) -> Vector4<T> {
+        Vector4::new(self.x, self.y, self.z, w)
+    }
+
+    pub fn zyx(&self) -> Self {
+        Vector::new(self.z, self.y, self.x)
+    }
+}
+
+impl<T: Scalar> From<[T; 4]> for Vector4<T> {
+    fn from([x, y, z, w]: [T; 4]) -> Self {
+        Self::new(x, y, z, w)
+    }
+}
+
+impl_vector_ops!(Vector4 { x, y, z, w });
+
+impl<T: Scalar> Mul<Vector4<T>> for &Matrix4<T> {
+    type Output = Vector4<T>;
+
+    /// Multiply the matrix by a column vector.
+    fn mul(self, rhs: Vector4<T>) -> Vector4<T> {
+        Vector4::new(
+            Vector4::from(self.row(0)).dot_product(&rhs),
+            Vector4::from(self.row(1)).dot_product(&rhs),
+            Vector4::from(self.row(2)).dot_product(&rhs),
+            Vector4::from(self.row(3)).dot_product(&rhs),
+        )
+    }
+}
+
+impl<T> Index<usize> for Vector4<T> {
+    type Output = T;
+
+    /// Index the components by axis: 0 for x up to 3 for w.
+    fn index(&self, index: usize) -> &T {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            3 => &self.w,
+            _ => panic!(
+                "Index error: axis should be in [0, 3], current index is {}",
+                index
+            ),
+        }
+    }
+}
+
+impl<T> IndexMut<usize> for Vector4<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            3 => &mut self.w,
+            _ => panic!(
+                "Index error: axis should be in [0, 3], current index is {}",
+                index
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Vector4;
+    use crate::math::{Matrix4, Vector};
+
+    #[test]
+    fn test_vector4() {
+        let a = Vector4::new(1.0, 2.0, 2.0, 4.0);
+        assert_eq!(a.length(), 5.0);
+        assert_eq!(a.dot_product(&Vector4::new(1.0, 1.0, 1.0, 1.0)), 9.0);
+        assert_eq!(a.homogeneous_divide(), Vector::new(0.25, 0.5, 0.5));
+        assert_eq!((a * 2.0 + a)[3], 12.0);
+        assert_eq!(a.xyz().zyx(), Vector::new(2.0, 2.0, 1.0));
+    }
+
+    #[test]
+    fn test_homogeneous() {
+        let mat = Matrix4::perspective(1.2, 1.5, 0.5, 20.0)
+            * Matrix4::translation(&Vector::new(1.0, -2.0, 0.5));
+        let p = Vector::new(0.3, 0.2, -4.0);
+        let d = Vector::new(0.3, 0.2, -4.0);
+
+        let clip = &mat * Vector4::point(&p);
+        assert!((clip.homogeneous_divide() - mat.transform_point(&p)).length() < 1e-6);
+        assert_eq!(
+            (&mat * Vector4::direction(&d)).xyz(),
+            mat.transform_direction(&d)
+        );
+    }
+}